@@ -1,3 +1,5 @@
+//the y axis of the clip space points up with wgpu and down with Vulkan, so this shader draws upside down on Vulkan
+
 struct VertexInput {
     @location(0) position: vec2<f32>,
 };
@@ -0,0 +1,33 @@
+use error_stack::{Report, Result};
+
+use core::clipboard::Clipboard;
+use core::error::ThrustlerError;
+
+/// The system clipboard, backed by arboard.
+pub struct ArboardClipboard {
+    clipboard: arboard::Clipboard,
+}
+
+impl ArboardClipboard {
+    pub fn new() -> Result<ArboardClipboard, ThrustlerError> {
+        let clipboard = arboard::Clipboard::new()
+            .map_err(|err| to_report("Can't open the system clipboard", err))?;
+        Ok(Self { clipboard })
+    }
+}
+
+impl Clipboard for ArboardClipboard {
+    fn get_text(&mut self) -> Result<String, ThrustlerError> {
+        self.clipboard.get_text()
+            .map_err(|err| to_report("Can't read text from the clipboard", err))
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ThrustlerError> {
+        self.clipboard.set_text(text)
+            .map_err(|err| to_report("Can't write text into the clipboard", err))
+    }
+}
+
+fn to_report(message: &str, err: arboard::Error) -> Report<ThrustlerError> {
+    Report::new(ThrustlerError::ClipboardError).attach_printable(format!("{message}: {err}"))
+}
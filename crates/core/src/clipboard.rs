@@ -0,0 +1,34 @@
+use error_stack::{Report, Result, ResultExt};
+
+use crate::error::ThrustlerError;
+
+/// Text clipboard which scenes access through [`SceneContext::clipboard`](crate::context::SceneContext::clipboard).
+pub trait Clipboard {
+    fn get_text(&mut self) -> Result<String, ThrustlerError>;
+    fn set_text(&mut self, text: &str) -> Result<(), ThrustlerError>;
+}
+
+/// Clipboard which lives inside the process, it's used when the system clipboard is unavailable and in tests.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Result<String, ThrustlerError> {
+        self.text.clone()
+            .ok_or(Report::new(ThrustlerError::ClipboardError))
+            .attach_printable("Clipboard is empty")
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ThrustlerError> {
+        self.text = Some(text.to_string());
+        Ok(())
+    }
+}
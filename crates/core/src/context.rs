@@ -0,0 +1,99 @@
+use crate::clipboard::Clipboard;
+use crate::input::actions::{ActionMap, Actions};
+use crate::input::InputState;
+use crate::scheduler::Scheduler;
+use crate::stats::FrameStats;
+use crate::time::TimeControl;
+use crate::window::{WindowControl, WindowMetrics};
+
+/// Engine state available to a scene inside its callbacks.
+pub struct SceneContext<'a> {
+    frame_stats: &'a FrameStats,
+    input: &'a InputState,
+    action_map: &'a mut ActionMap,
+    time_control: &'a mut TimeControl,
+    scheduler: &'a mut Scheduler,
+    window: &'a WindowControl,
+    window_metrics: WindowMetrics,
+    clipboard: &'a mut dyn Clipboard,
+}
+
+/// Engine state a [`SceneContext`] is built from, the engine fills it for every callback.
+pub struct SceneContextParts<'a> {
+    pub frame_stats: &'a FrameStats,
+    pub input: &'a InputState,
+    pub action_map: &'a mut ActionMap,
+    pub time_control: &'a mut TimeControl,
+    pub scheduler: &'a mut Scheduler,
+    pub window: &'a WindowControl,
+    pub window_metrics: WindowMetrics,
+    pub clipboard: &'a mut dyn Clipboard,
+}
+
+impl<'a> SceneContext<'a> {
+    pub fn new(parts: SceneContextParts<'a>) -> Self {
+        let SceneContextParts {
+            frame_stats,
+            input,
+            action_map,
+            time_control,
+            scheduler,
+            window,
+            window_metrics,
+            clipboard,
+        } = parts;
+        Self {
+            frame_stats,
+            input,
+            action_map,
+            time_control,
+            scheduler,
+            window,
+            window_metrics,
+            clipboard,
+        }
+    }
+
+    pub fn frame_stats(&self) -> &FrameStats {
+        self.frame_stats
+    }
+
+    pub fn input(&self) -> &InputState {
+        self.input
+    }
+
+    /// Named actions evaluated against the current input state.
+    pub fn actions(&self) -> Actions<'_> {
+        Actions::new(self.action_map, self.input)
+    }
+
+    /// Bindings of named actions, they can be changed at runtime.
+    pub fn action_map(&mut self) -> &mut ActionMap {
+        self.action_map
+    }
+
+    /// Pause, time scale and single step controls of the game loop, they are shared by all scenes.
+    pub fn time_control(&mut self) -> &mut TimeControl {
+        self.time_control
+    }
+
+    /// Timers of the scene, they are cancelled when the scene is destroyed.
+    pub fn scheduler(&mut self) -> &mut Scheduler {
+        self.scheduler
+    }
+
+    /// Changes of the window, e.g. its title.
+    pub fn window(&self) -> &WindowControl {
+        self.window
+    }
+
+    /// Size and scale factor of the window the scene is drawn in.
+    pub fn window_metrics(&self) -> WindowMetrics {
+        self.window_metrics
+    }
+
+    /// Text clipboard of the system, or of the engine if the system one is unavailable.
+    pub fn clipboard(&mut self) -> &mut dyn Clipboard {
+        self.clipboard
+    }
+}
@@ -6,7 +6,13 @@ use error_stack::Context;
 pub enum ThrustlerError {
     WindowError,
     GraphicalBackendError,
+    /// The frame can't be drawn now, but drawing may succeed if it is retried
+    RecoverableBackendError,
     EngineError,
+    GamepadError,
+    ClipboardError,
+    /// A shader supplied at runtime can't be compiled or used
+    ShaderError,
 }
 
 impl Display for ThrustlerError {
@@ -14,7 +20,11 @@ impl Display for ThrustlerError {
         let msg = match self {
             Self::WindowError => "Unable error",
             Self::GraphicalBackendError => "Graphical backend error",
-            Self::EngineError => "Engine error"
+            Self::RecoverableBackendError => "Recoverable graphical backend error",
+            Self::EngineError => "Engine error",
+            Self::GamepadError => "Gamepad error",
+            Self::ClipboardError => "Clipboard error",
+            Self::ShaderError => "Shader error",
         };
         write!(f, "{msg}")
     }
@@ -1,9 +1,20 @@
+use std::sync::Arc;
+
 use uuid::Uuid;
 
+use crate::context::SceneContext;
+use crate::input::InputEvent;
+use crate::lifecycle::LifecycleEvent;
+use crate::scheduler::TimerEvent;
+use crate::shader::Shader;
+
 #[derive(Debug)]
 pub struct GameObject {
     pub id: Uuid,
     pub vertices: Vec<Vertex>,
+    pub stencil: StencilMode,
+    /// The shader of the object, the backend's built-in shader is used if it's `None`
+    pub shader: Option<Arc<Shader>>,
 }
 
 impl GameObject {
@@ -11,6 +22,44 @@ impl GameObject {
         Self {
             id: Uuid::new_v4(),
             vertices,
+            stencil: StencilMode::None,
+            shader: None,
+        }
+    }
+
+    pub fn with_stencil(mut self, stencil: StencilMode) -> Self {
+        self.stencil = stencil;
+        self
+    }
+
+    /// Draws the object with the shader, objects may share it to share the pipeline as well.
+    pub fn with_shader(mut self, shader: Arc<Shader>) -> Self {
+        self.shader = Some(shader);
+        self
+    }
+}
+
+/// How a game object takes part in stencil masking. Objects are drawn in the scene order
+/// and the stencil buffer is cleared to 0 at the beginning of every frame, so masks have to go before the objects they clip.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum StencilMode {
+    /// Drawn as usual, the stencil buffer isn't used
+    #[default]
+    None,
+    /// Writes the value into the stencil buffer where the object covers it, the object itself isn't visible
+    Mask(u8),
+    /// Drawn only where the stencil buffer holds the value
+    Inside(u8),
+    /// Drawn only where the stencil buffer doesn't hold the value
+    Outside(u8),
+}
+
+impl StencilMode {
+    /// The value which is written or compared, it's 0 for [`StencilMode::None`].
+    pub fn reference(&self) -> u32 {
+        match self {
+            StencilMode::None => 0,
+            StencilMode::Mask(value) | StencilMode::Inside(value) | StencilMode::Outside(value) => *value as u32,
         }
     }
 }
@@ -35,8 +84,14 @@ impl Vertex {
 }
 
 pub trait Scene {
-    fn on_start(&mut self);
-    fn on_update(&mut self);
-    fn on_destroy(&mut self);
+    fn on_start(&mut self, context: &mut SceneContext);
+    fn on_update(&mut self, context: &mut SceneContext);
+    fn on_destroy(&mut self, context: &mut SceneContext);
+    /// Called before `on_update` for every timer fired during the update.
+    fn on_timer(&mut self, _event: TimerEvent, _context: &mut SceneContext) {}
+    /// Called as soon as an input event comes from the window, the input state is already updated by then.
+    fn on_input(&mut self, _event: &InputEvent, _context: &mut SceneContext) {}
+    /// Called when the application is suspended or resumed, or the window of the scene changes its state.
+    fn on_lifecycle(&mut self, _event: LifecycleEvent, _context: &mut SceneContext) {}
     fn get_scene_objects(&self) -> &Vec<GameObject>;
 }
\ No newline at end of file
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use error_stack::{Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ThrustlerError;
+use crate::input::gamepad::{GamepadAxis, GamepadButton};
+use crate::input::InputState;
+use crate::input::keyboard::KeyCode;
+use crate::input::mouse::MouseButton;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonBinding {
+    Key(KeyCode),
+    MouseButton(MouseButton),
+    GamepadButton(GamepadButton),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AxisBinding {
+    Keys { negative: KeyCode, positive: KeyCode },
+    GamepadAxis(GamepadAxis),
+}
+
+/// The y component of two-dimensional axes points up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis2dBinding {
+    Keys { up: KeyCode, down: KeyCode, left: KeyCode, right: KeyCode },
+    GamepadStick { x: GamepadAxis, y: GamepadAxis },
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisAction {
+    /// Values with a smaller magnitude are reported as zero, the rest are rescaled to start from zero
+    #[serde(default)]
+    pub dead_zone: f32,
+    #[serde(default)]
+    pub bindings: Vec<AxisBinding>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Axis2dAction {
+    #[serde(default)]
+    pub dead_zone: f32,
+    #[serde(default)]
+    pub bindings: Vec<Axis2dBinding>,
+}
+
+/// Named actions and the inputs they are bound to. It can be loaded from a TOML file:
+///
+/// ```toml
+/// [buttons]
+/// jump = [{ key = "Space" }, { gamepad_button = "South" }]
+///
+/// [axes.move_x]
+/// dead_zone = 0.2
+/// bindings = [{ keys = { negative = "KeyA", positive = "KeyD" } }, { gamepad_axis = "LeftStickX" }]
+///
+/// [axes_2d.move]
+/// bindings = [{ keys = { up = "KeyW", down = "KeyS", left = "KeyA", right = "KeyD" } }]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionMap {
+    #[serde(default)]
+    pub buttons: HashMap<String, Vec<ButtonBinding>>,
+    #[serde(default)]
+    pub axes: HashMap<String, AxisAction>,
+    #[serde(default)]
+    pub axes_2d: HashMap<String, Axis2dAction>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<ActionMap, ThrustlerError> {
+        let config = fs::read_to_string(path.as_ref())
+            .attach_printable_lazy(|| format!("Can't read action map file {:?}", path.as_ref()))
+            .change_context(ThrustlerError::EngineError)?;
+        Self::from_toml(&config)
+    }
+
+    pub fn from_toml(config: &str) -> Result<ActionMap, ThrustlerError> {
+        toml::from_str(config)
+            .attach_printable("Malformed action map")
+            .change_context(ThrustlerError::EngineError)
+    }
+
+    /// Writes the current bindings, so bindings changed at runtime can be kept.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ThrustlerError> {
+        let config = toml::to_string_pretty(self)
+            .attach_printable("Can't serialize action map")
+            .change_context(ThrustlerError::EngineError)?;
+        fs::write(path.as_ref(), config)
+            .attach_printable_lazy(|| format!("Can't write action map file {:?}", path.as_ref()))
+            .change_context(ThrustlerError::EngineError)
+    }
+
+    pub fn bind_button(&mut self, action: &str, binding: ButtonBinding) {
+        self.buttons.entry(action.to_string()).or_default().push(binding);
+    }
+
+    pub fn bind_axis(&mut self, action: &str, binding: AxisBinding) {
+        self.axes.entry(action.to_string()).or_default().bindings.push(binding);
+    }
+
+    pub fn bind_axis_2d(&mut self, action: &str, binding: Axis2dBinding) {
+        self.axes_2d.entry(action.to_string()).or_default().bindings.push(binding);
+    }
+
+    pub fn set_dead_zone(&mut self, action: &str, dead_zone: f32) {
+        if let Some(axis) = self.axes.get_mut(action) {
+            axis.dead_zone = dead_zone;
+        }
+        if let Some(axis) = self.axes_2d.get_mut(action) {
+            axis.dead_zone = dead_zone;
+        }
+    }
+
+    /// Removes all bindings of the action, so it can be bound to other inputs.
+    pub fn unbind(&mut self, action: &str) {
+        self.buttons.remove(action);
+        self.axes.remove(action);
+        self.axes_2d.remove(action);
+    }
+}
+
+/// Actions evaluated against the current input state.
+pub struct Actions<'a> {
+    map: &'a ActionMap,
+    input: &'a InputState,
+}
+
+impl<'a> Actions<'a> {
+    pub fn new(map: &'a ActionMap, input: &'a InputState) -> Self {
+        Self { map, input }
+    }
+
+    pub fn is_down(&self, action: &str) -> bool {
+        self.any_button(action, |binding| match binding {
+            ButtonBinding::Key(key) => self.input.keyboard().is_key_down(*key),
+            ButtonBinding::MouseButton(button) => self.input.mouse().is_button_down(*button),
+            ButtonBinding::GamepadButton(button) => self.input.gamepads().any(|gamepad| gamepad.is_button_down(*button)),
+        })
+    }
+
+    pub fn was_pressed(&self, action: &str) -> bool {
+        self.any_button(action, |binding| match binding {
+            ButtonBinding::Key(key) => self.input.keyboard().was_pressed(*key),
+            ButtonBinding::MouseButton(button) => self.input.mouse().was_pressed(*button),
+            ButtonBinding::GamepadButton(button) => self.input.gamepads().any(|gamepad| gamepad.was_pressed(*button)),
+        })
+    }
+
+    pub fn was_released(&self, action: &str) -> bool {
+        self.any_button(action, |binding| match binding {
+            ButtonBinding::Key(key) => self.input.keyboard().was_released(*key),
+            ButtonBinding::MouseButton(button) => self.input.mouse().was_released(*button),
+            ButtonBinding::GamepadButton(button) => self.input.gamepads().any(|gamepad| gamepad.was_released(*button)),
+        })
+    }
+
+    /// Value from -1.0 to 1.0, the binding with the largest magnitude wins.
+    pub fn axis(&self, action: &str) -> f32 {
+        let Some(axis) = self.map.axes.get(action) else {
+            return 0.0;
+        };
+
+        let value = axis.bindings.iter()
+            .map(|binding| match binding {
+                AxisBinding::Keys { negative, positive } => self.key_axis(*negative, *positive),
+                AxisBinding::GamepadAxis(gamepad_axis) => self.gamepad_axis(*gamepad_axis),
+            })
+            .fold(0.0, |max: f32, value| if value.abs() > max.abs() { value } else { max });
+
+        apply_dead_zone(value, axis.dead_zone)
+    }
+
+    /// Vector with a length up to 1.0, the binding with the largest length wins.
+    pub fn axis_2d(&self, action: &str) -> [f32; 2] {
+        let Some(axis) = self.map.axes_2d.get(action) else {
+            return [0.0, 0.0];
+        };
+
+        let [x, y] = axis.bindings.iter()
+            .map(|binding| match binding {
+                Axis2dBinding::Keys { up, down, left, right } => [
+                    self.key_axis(*left, *right),
+                    self.key_axis(*down, *up),
+                ],
+                Axis2dBinding::GamepadStick { x, y } => [self.gamepad_axis(*x), self.gamepad_axis(*y)],
+            })
+            .fold([0.0, 0.0], |max: [f32; 2], value| if length(value) > length(max) { value } else { max });
+
+        let value_length = length([x, y]);
+        if value_length == 0.0 {
+            return [0.0, 0.0];
+        }
+        //the radial dead zone keeps the direction of the stick
+        let scaled_length = apply_dead_zone(value_length.min(1.0), axis.dead_zone);
+        [x / value_length * scaled_length, y / value_length * scaled_length]
+    }
+
+    fn any_button(&self, action: &str, is_active: impl Fn(&ButtonBinding) -> bool) -> bool {
+        self.map.buttons.get(action)
+            .map(|bindings| bindings.iter().any(is_active))
+            .unwrap_or(false)
+    }
+
+    fn key_axis(&self, negative: KeyCode, positive: KeyCode) -> f32 {
+        let keyboard = self.input.keyboard();
+        let mut value = 0.0;
+        if keyboard.is_key_down(negative) {
+            value -= 1.0;
+        }
+        if keyboard.is_key_down(positive) {
+            value += 1.0;
+        }
+        value
+    }
+
+    fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.input.gamepads()
+            .map(|gamepad| gamepad.axis(axis))
+            .fold(0.0, |max: f32, value| if value.abs() > max.abs() { value } else { max })
+    }
+}
+
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    let dead_zone = dead_zone.clamp(0.0, 0.99);
+    if value.abs() < dead_zone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+    }
+}
+
+fn length([x, y]: [f32; 2]) -> f32 {
+    (x * x + y * y).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::{InputEvent, Viewport};
+    use crate::input::gamepad::{GamepadEvent, GamepadId};
+    use crate::input::keyboard::{Key, KeyboardEvent, KeyState, Modifiers};
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn input_with_axes(values: &[(GamepadAxis, f32)]) -> InputState {
+        let mut input = InputState::new(Viewport::default());
+        for (axis, value) in values {
+            input.apply(&InputEvent::Gamepad(GamepadEvent::Axis { id: GamepadId(0), axis: *axis, value: *value }));
+        }
+        input
+    }
+
+    fn press(input: &mut InputState, key: KeyCode) {
+        input.apply(&InputEvent::Keyboard(KeyboardEvent {
+            physical_key: key,
+            logical_key: Key::Unidentified,
+            state: KeyState::Pressed,
+            modifiers: Modifiers::default(),
+        }));
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < EPSILON, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn dead_zone_zeroes_small_values_and_rescales_the_rest() {
+        let mut map = ActionMap::new();
+        map.bind_axis("steer", AxisBinding::GamepadAxis(GamepadAxis::LeftStickX));
+        map.set_dead_zone("steer", 0.2);
+
+        let input = input_with_axes(&[(GamepadAxis::LeftStickX, 0.15)]);
+        assert_eq!(Actions::new(&map, &input).axis("steer"), 0.0);
+
+        let input = input_with_axes(&[(GamepadAxis::LeftStickX, -0.6)]);
+        assert_close(Actions::new(&map, &input).axis("steer"), -0.5);
+
+        let input = input_with_axes(&[(GamepadAxis::LeftStickX, 1.0)]);
+        assert_close(Actions::new(&map, &input).axis("steer"), 1.0);
+    }
+
+    #[test]
+    fn axis_takes_the_binding_with_the_largest_magnitude() {
+        let mut map = ActionMap::new();
+        map.bind_axis("steer", AxisBinding::Keys { negative: KeyCode::KeyA, positive: KeyCode::KeyD });
+        map.bind_axis("steer", AxisBinding::GamepadAxis(GamepadAxis::LeftStickX));
+
+        let mut input = input_with_axes(&[(GamepadAxis::LeftStickX, 0.4)]);
+        assert_close(Actions::new(&map, &input).axis("steer"), 0.4);
+        press(&mut input, KeyCode::KeyA);
+        assert_close(Actions::new(&map, &input).axis("steer"), -1.0);
+        assert_eq!(Actions::new(&map, &input).axis("unknown"), 0.0);
+    }
+
+    #[test]
+    fn axis_2d_is_normalised() {
+        let mut map = ActionMap::new();
+        map.bind_axis_2d("move", Axis2dBinding::Keys { up: KeyCode::KeyW, down: KeyCode::KeyS, left: KeyCode::KeyA, right: KeyCode::KeyD });
+
+        //diagonal keys don't move faster than a single key
+        let mut input = InputState::new(Viewport::default());
+        press(&mut input, KeyCode::KeyW);
+        press(&mut input, KeyCode::KeyD);
+        let [x, y] = Actions::new(&map, &input).axis_2d("move");
+        assert_close(x, std::f32::consts::FRAC_1_SQRT_2);
+        assert_close(y, std::f32::consts::FRAC_1_SQRT_2);
+        assert_close(length([x, y]), 1.0);
+
+        let mut input = InputState::new(Viewport::default());
+        press(&mut input, KeyCode::KeyS);
+        assert_eq!(Actions::new(&map, &input).axis_2d("move"), [0.0, -1.0]);
+    }
+
+    #[test]
+    fn axis_2d_dead_zone_is_radial() {
+        let mut map = ActionMap::new();
+        map.bind_axis_2d("look", Axis2dBinding::GamepadStick { x: GamepadAxis::RightStickX, y: GamepadAxis::RightStickY });
+        map.set_dead_zone("look", 0.5);
+
+        //both components are below the dead zone, but the length isn't
+        let input = input_with_axes(&[(GamepadAxis::RightStickX, 0.45), (GamepadAxis::RightStickY, 0.6)]);
+        let [x, y] = Actions::new(&map, &input).axis_2d("look");
+        assert_close(length([x, y]), 0.5);
+        //the direction is kept
+        assert_close(x / y, 0.75);
+
+        let input = input_with_axes(&[(GamepadAxis::RightStickX, 0.3), (GamepadAxis::RightStickY, 0.3)]);
+        assert_eq!(Actions::new(&map, &input).axis_2d("look"), [0.0, 0.0]);
+
+        //stick values outside the circle are clamped to the unit length
+        let input = input_with_axes(&[(GamepadAxis::RightStickX, 1.0), (GamepadAxis::RightStickY, 1.0)]);
+        assert_close(length(Actions::new(&map, &input).axis_2d("look")), 1.0);
+    }
+
+    #[test]
+    fn toml_round_trip_keeps_bindings() {
+        let mut map = ActionMap::new();
+        map.bind_button("jump", ButtonBinding::Key(KeyCode::Space));
+        map.bind_button("jump", ButtonBinding::GamepadButton(GamepadButton::South));
+        map.bind_button("shoot", ButtonBinding::MouseButton(MouseButton::Left));
+        map.bind_axis("steer", AxisBinding::Keys { negative: KeyCode::KeyA, positive: KeyCode::KeyD });
+        map.bind_axis("steer", AxisBinding::GamepadAxis(GamepadAxis::LeftStickX));
+        map.set_dead_zone("steer", 0.25);
+        map.bind_axis_2d("move", Axis2dBinding::GamepadStick { x: GamepadAxis::LeftStickX, y: GamepadAxis::LeftStickY });
+
+        let path = std::env::temp_dir().join(format!("thrustler-actions-{}.toml", std::process::id()));
+        map.save(&path).unwrap();
+        let loaded = ActionMap::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded, map);
+    }
+
+    #[test]
+    fn from_toml_reads_the_documented_format() {
+        let map = ActionMap::from_toml(r#"
+            [buttons]
+            jump = [{ key = "Space" }, { gamepad_button = "South" }]
+
+            [axes.move_x]
+            dead_zone = 0.2
+            bindings = [{ keys = { negative = "KeyA", positive = "KeyD" } }, { gamepad_axis = "LeftStickX" }]
+
+            [axes_2d.move]
+            bindings = [{ keys = { up = "KeyW", down = "KeyS", left = "KeyA", right = "KeyD" } }]
+        "#).unwrap();
+
+        assert_eq!(map.buttons["jump"], vec![ButtonBinding::Key(KeyCode::Space), ButtonBinding::GamepadButton(GamepadButton::South)]);
+        assert_eq!(map.axes["move_x"].dead_zone, 0.2);
+        assert_eq!(map.axes["move_x"].bindings.len(), 2);
+        assert_eq!(map.axes_2d["move"].dead_zone, 0.0);
+        assert!(ActionMap::from_toml("buttons = 1").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::Viewport;
+
+/// Files dragged onto a window from the system, every file comes as a separate event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FileDropEvent {
+    /// A file is dragged over the window. The position is in window pixels, `None` if the cursor position isn't known yet
+    Hovered { path: PathBuf, position: Option<[f32; 2]> },
+    /// The hovered files left the window or the drag was cancelled
+    HoverCancelled,
+    Dropped { path: PathBuf, position: Option<[f32; 2]> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedFile {
+    pub path: PathBuf,
+    /// Cursor position in window pixels at the moment of the event
+    pub position: Option<[f32; 2]>,
+}
+
+/// File drop state which is polled by scenes. Dropped files last for one update.
+#[derive(Debug, Default, Clone)]
+pub struct FileDropState {
+    hovered: Vec<DroppedFile>,
+    dropped: Vec<DroppedFile>,
+    viewport: Viewport,
+}
+
+impl FileDropState {
+    pub fn new(viewport: Viewport) -> Self {
+        Self {
+            viewport,
+            ..Self::default()
+        }
+    }
+
+    /// Files which are being dragged over the window, e.g. to highlight the drop target.
+    pub fn hovered(&self) -> &[DroppedFile] {
+        &self.hovered
+    }
+
+    /// Files dropped since the previous update.
+    pub fn dropped(&self) -> &[DroppedFile] {
+        &self.dropped
+    }
+
+    /// Converts a drop position into the coordinate space of [`Vertex::position`](crate::game_objects::Vertex).
+    pub fn to_world(&self, position: [f32; 2]) -> [f32; 2] {
+        self.viewport.to_world(position)
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    pub fn apply(&mut self, event: &FileDropEvent) {
+        match event {
+            FileDropEvent::Hovered { path, position } => {
+                self.hovered.push(DroppedFile { path: path.clone(), position: *position });
+            }
+            FileDropEvent::HoverCancelled => self.hovered.clear(),
+            FileDropEvent::Dropped { path, position } => {
+                //a dropped file isn't hovered anymore
+                self.hovered.retain(|file| file.path != *path);
+                self.dropped.push(DroppedFile { path: path.clone(), position: *position });
+            }
+        }
+    }
+
+    /// Forgets files dropped during the update, it's called by the engine after every update.
+    pub fn end_update(&mut self) {
+        self.dropped.clear();
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::mouse::ButtonState;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GamepadId(pub u32);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Stick axes go from -1.0 to 1.0, the y axes point up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftZ,
+    RightZ,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GamepadEvent {
+    Connected { id: GamepadId, name: String },
+    Disconnected { id: GamepadId },
+    Button { id: GamepadId, button: GamepadButton, state: ButtonState },
+    Axis { id: GamepadId, axis: GamepadAxis, value: f32 },
+}
+
+impl GamepadEvent {
+    pub fn id(&self) -> GamepadId {
+        match self {
+            Self::Connected { id, .. } => *id,
+            Self::Disconnected { id } => *id,
+            Self::Button { id, .. } => *id,
+            Self::Axis { id, .. } => *id,
+        }
+    }
+}
+
+/// Source of gamepad events which the engine polls before every frame.
+pub trait GamepadSource {
+    fn poll_events(&mut self) -> Vec<GamepadEvent>;
+}
+
+/// A gamepad driven from code, so controller logic can be tested without a real device.
+/// Events sent through [`VirtualGamepadHandle`] are delivered on the next poll.
+pub struct VirtualGamepad {
+    events: Arc<Mutex<VecDeque<GamepadEvent>>>,
+}
+
+/// Drives a [`VirtualGamepad`], it can be cloned and sent to other threads.
+#[derive(Clone)]
+pub struct VirtualGamepadHandle {
+    id: GamepadId,
+    events: Arc<Mutex<VecDeque<GamepadEvent>>>,
+}
+
+impl VirtualGamepad {
+    /// The id shouldn't clash with ids of real gamepads, which are numbered from zero.
+    pub fn new(id: GamepadId) -> (VirtualGamepad, VirtualGamepadHandle) {
+        let events = Arc::new(Mutex::new(VecDeque::new()));
+        let gamepad = VirtualGamepad {
+            events: events.clone(),
+        };
+        (gamepad, VirtualGamepadHandle { id, events })
+    }
+}
+
+impl GamepadSource for VirtualGamepad {
+    fn poll_events(&mut self) -> Vec<GamepadEvent> {
+        self.events.lock()
+            .map(|mut events| events.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl VirtualGamepadHandle {
+    pub fn id(&self) -> GamepadId {
+        self.id
+    }
+
+    pub fn connect(&self, name: &str) {
+        self.send(GamepadEvent::Connected { id: self.id, name: name.to_string() });
+    }
+
+    pub fn disconnect(&self) {
+        self.send(GamepadEvent::Disconnected { id: self.id });
+    }
+
+    pub fn press(&self, button: GamepadButton) {
+        self.send(GamepadEvent::Button { id: self.id, button, state: ButtonState::Pressed });
+    }
+
+    pub fn release(&self, button: GamepadButton) {
+        self.send(GamepadEvent::Button { id: self.id, button, state: ButtonState::Released });
+    }
+
+    pub fn set_axis(&self, axis: GamepadAxis, value: f32) {
+        self.send(GamepadEvent::Axis { id: self.id, axis, value: value.clamp(-1.0, 1.0) });
+    }
+
+    fn send(&self, event: GamepadEvent) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push_back(event);
+        }
+    }
+}
+
+/// State of a single gamepad. "Pressed" and "released" states last for one update.
+#[derive(Debug, Default, Clone)]
+pub struct GamepadState {
+    name: String,
+    down: HashSet<GamepadButton>,
+    pressed: HashSet<GamepadButton>,
+    released: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+impl GamepadState {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_button_down(&self, button: GamepadButton) -> bool {
+        self.down.contains(&button)
+    }
+
+    pub fn was_pressed(&self, button: GamepadButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn was_released(&self, button: GamepadButton) -> bool {
+        self.released.contains(&button)
+    }
+
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    pub fn apply(&mut self, event: &GamepadEvent) {
+        match event {
+            GamepadEvent::Button { button, state: ButtonState::Pressed, .. } => {
+                if self.down.insert(*button) {
+                    self.pressed.insert(*button);
+                }
+            }
+            GamepadEvent::Button { button, state: ButtonState::Released, .. } => {
+                if self.down.remove(button) {
+                    self.released.insert(*button);
+                }
+            }
+            GamepadEvent::Axis { axis, value, .. } => {
+                self.axes.insert(*axis, *value);
+            }
+            GamepadEvent::Connected { .. } | GamepadEvent::Disconnected { .. } => {}
+        }
+    }
+
+    /// Forgets buttons pressed and released during the update, it's called by the engine after every update.
+    pub fn end_update(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+}
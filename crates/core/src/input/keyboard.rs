@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+/// Position of a key on the keyboard, it doesn't depend on the keyboard layout.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyCode {
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Backquote,
+    Backslash,
+    BracketLeft,
+    BracketRight,
+    Comma,
+    Equal,
+    Minus,
+    Period,
+    Quote,
+    Semicolon,
+    Slash,
+    AltLeft,
+    AltRight,
+    Backspace,
+    CapsLock,
+    ContextMenu,
+    ControlLeft,
+    ControlRight,
+    Enter,
+    ShiftLeft,
+    ShiftRight,
+    SuperLeft,
+    SuperRight,
+    Space,
+    Tab,
+    Delete,
+    End,
+    Home,
+    Insert,
+    PageDown,
+    PageUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    NumLock,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadDecimal,
+    NumpadDivide,
+    NumpadEnter,
+    NumpadMultiply,
+    NumpadSubtract,
+    Escape,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Unidentified,
+}
+
+/// Keys which don't produce a character.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NamedKey {
+    Alt,
+    CapsLock,
+    Control,
+    Shift,
+    Super,
+    Enter,
+    Tab,
+    Space,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    End,
+    Home,
+    PageDown,
+    PageUp,
+    Backspace,
+    Delete,
+    Insert,
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+}
+
+/// Meaning of a key under the current keyboard layout and modifiers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Key {
+    Character(String),
+    Named(NamedKey),
+    Unidentified,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyState {
+    Pressed,
+    Released,
+    /// The key is held down and the system repeats the press
+    Repeated,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub super_key: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardEvent {
+    pub physical_key: KeyCode,
+    pub logical_key: Key,
+    pub state: KeyState,
+    pub modifiers: Modifiers,
+}
+
+/// Keyboard state which is polled by scenes. "Pressed" and "released" states last for one update.
+#[derive(Debug, Default, Clone)]
+pub struct KeyboardState {
+    down: HashSet<KeyCode>,
+    pressed: HashSet<KeyCode>,
+    released: HashSet<KeyCode>,
+    modifiers: Modifiers,
+}
+
+impl KeyboardState {
+    pub fn is_key_down(&self, key: KeyCode) -> bool {
+        self.down.contains(&key)
+    }
+
+    /// True if the key was pressed since the previous update, repeats are not taken into account.
+    pub fn was_pressed(&self, key: KeyCode) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    pub fn was_released(&self, key: KeyCode) -> bool {
+        self.released.contains(&key)
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub fn apply(&mut self, event: &KeyboardEvent) {
+        self.modifiers = event.modifiers;
+        match event.state {
+            KeyState::Pressed => {
+                if self.down.insert(event.physical_key) {
+                    self.pressed.insert(event.physical_key);
+                }
+            }
+            KeyState::Released => {
+                if self.down.remove(&event.physical_key) {
+                    self.released.insert(event.physical_key);
+                }
+            }
+            KeyState::Repeated => {}
+        }
+    }
+
+    /// Releases all keys, e.g. when the window loses focus and their release events go to another window.
+    pub fn release_all(&mut self) {
+        self.released.extend(self.down.drain());
+        self.modifiers = Modifiers::default();
+    }
+
+    /// Forgets keys pressed and released during the update, it's called by the engine after every update.
+    pub fn end_update(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_event(key: KeyCode, state: KeyState) -> KeyboardEvent {
+        KeyboardEvent {
+            physical_key: key,
+            logical_key: Key::Unidentified,
+            state,
+            modifiers: Modifiers { shift: true, ..Modifiers::default() },
+        }
+    }
+
+    #[test]
+    fn keys_are_released_when_focus_is_lost() {
+        let mut keyboard = KeyboardState::default();
+        keyboard.apply(&key_event(KeyCode::KeyW, KeyState::Pressed));
+        keyboard.end_update();
+        assert!(keyboard.is_key_down(KeyCode::KeyW));
+
+        keyboard.release_all();
+        assert!(!keyboard.is_key_down(KeyCode::KeyW));
+        assert!(keyboard.was_released(KeyCode::KeyW));
+        assert_eq!(keyboard.modifiers(), Modifiers::default());
+
+        keyboard.end_update();
+        assert!(!keyboard.was_released(KeyCode::KeyW));
+        //the key can be pressed again when the focus is back
+        keyboard.apply(&key_event(KeyCode::KeyW, KeyState::Pressed));
+        assert!(keyboard.was_pressed(KeyCode::KeyW));
+    }
+}
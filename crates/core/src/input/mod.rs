@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::file_drop::{FileDropEvent, FileDropState};
+use crate::input::gamepad::{GamepadEvent, GamepadId, GamepadState};
+use crate::input::keyboard::{KeyboardEvent, KeyboardState};
+use crate::input::mouse::{MouseEvent, MouseState};
+use crate::input::text::{TextEvent, TextState};
+use crate::input::touch::{TouchEvent, TouchState};
+use crate::Size;
+
+pub mod actions;
+pub mod file_drop;
+pub mod gamepad;
+pub mod keyboard;
+pub mod mouse;
+pub mod text;
+pub mod touch;
+
+/// Input events forwarded from a window to the engine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Keyboard(KeyboardEvent),
+    Mouse(MouseEvent),
+    Gamepad(GamepadEvent),
+    Touch(TouchEvent),
+    Text(TextEvent),
+    FileDrop(FileDropEvent),
+}
+
+/// Maps window pixels into the coordinate space of [`Vertex::position`](crate::game_objects::Vertex),
+/// where both axes go from -1.0 to 1.0 and the direction of the y axis depends on the backend.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Viewport {
+    pub size: Size,
+    pub y_axis_up: bool,
+}
+
+impl Viewport {
+    pub fn new(size: Size, y_axis_up: bool) -> Self {
+        Self { size, y_axis_up }
+    }
+
+    pub fn to_world(&self, position: [f32; 2]) -> [f32; 2] {
+        let [width, height]: [f32; 2] = self.size.into();
+        let x = position[0] / width.max(1.0) * 2.0 - 1.0;
+        let y = position[1] / height.max(1.0) * 2.0 - 1.0;
+        if self.y_axis_up {
+            [x, -y]
+        } else {
+            [x, y]
+        }
+    }
+}
+
+/// State of input devices which is available to scenes.
+#[derive(Debug, Default, Clone)]
+pub struct InputState {
+    keyboard: KeyboardState,
+    mouse: MouseState,
+    gamepads: HashMap<GamepadId, GamepadState>,
+    touch: TouchState,
+    text: TextState,
+    file_drop: FileDropState,
+}
+
+impl InputState {
+    pub fn new(viewport: Viewport) -> Self {
+        Self {
+            keyboard: KeyboardState::default(),
+            mouse: MouseState::new(viewport),
+            gamepads: HashMap::new(),
+            touch: TouchState::new(viewport),
+            text: TextState::default(),
+            file_drop: FileDropState::new(viewport),
+        }
+    }
+
+    pub fn keyboard(&self) -> &KeyboardState {
+        &self.keyboard
+    }
+
+    pub fn mouse(&self) -> &MouseState {
+        &self.mouse
+    }
+
+    pub fn touch(&self) -> &TouchState {
+        &self.touch
+    }
+
+    pub fn text(&self) -> &TextState {
+        &self.text
+    }
+
+    pub fn file_drop(&self) -> &FileDropState {
+        &self.file_drop
+    }
+
+    pub fn gamepad(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.gamepads.get(&id)
+    }
+
+    /// States of all connected gamepads.
+    pub fn gamepads(&self) -> impl Iterator<Item=&GamepadState> {
+        self.gamepads.values()
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.mouse.set_viewport(viewport);
+        self.touch.set_viewport(viewport);
+        self.file_drop.set_viewport(viewport);
+    }
+
+    /// Moves the input time forward, it's called by the engine before every update.
+    pub fn advance(&mut self, delta: Duration) {
+        self.touch.advance(delta);
+    }
+
+    /// Releases held keys, the window won't get their release events anymore. It's called by the engine.
+    pub fn focus_lost(&mut self) {
+        self.keyboard.release_all();
+    }
+
+    pub fn apply(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Keyboard(keyboard_event) => self.keyboard.apply(keyboard_event),
+            InputEvent::Mouse(mouse_event) => self.mouse.apply(mouse_event),
+            InputEvent::Touch(touch_event) => self.touch.apply(touch_event),
+            InputEvent::Text(text_event) => self.text.apply(text_event),
+            InputEvent::FileDrop(file_drop_event) => self.file_drop.apply(file_drop_event),
+            InputEvent::Gamepad(GamepadEvent::Connected { id, name }) => {
+                self.gamepads.insert(*id, GamepadState::new(name));
+            }
+            InputEvent::Gamepad(GamepadEvent::Disconnected { id }) => {
+                self.gamepads.remove(id);
+            }
+            InputEvent::Gamepad(gamepad_event) => {
+                //events of gamepads which were connected before the start come without a connection event
+                self.gamepads.entry(gamepad_event.id()).or_default().apply(gamepad_event);
+            }
+        }
+    }
+
+    /// Called by the engine after every update.
+    pub fn end_update(&mut self) {
+        self.keyboard.end_update();
+        self.mouse.end_update();
+        self.touch.end_update();
+        self.text.end_update();
+        self.file_drop.end_update();
+        self.gamepads.values_mut().for_each(|gamepad| gamepad.end_update());
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::Viewport;
+
+/// Pixel scroll deltas are converted into lines with this ratio for [`MouseState::scroll`].
+pub const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    Other(u16),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ButtonState {
+    Pressed,
+    Released,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ScrollDelta {
+    Lines([f32; 2]),
+    Pixels([f32; 2]),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MouseEvent {
+    /// Cursor position in window pixels, the origin is the top left corner
+    CursorMoved { position: [f32; 2] },
+    CursorEntered,
+    CursorLeft,
+    Button { button: MouseButton, state: ButtonState },
+    Wheel { delta: ScrollDelta },
+    /// Raw movement of the mouse, it keeps coming when the cursor is locked or stopped by the screen edge
+    Motion { delta: [f32; 2] },
+}
+
+/// Mouse state which is polled by scenes. "Pressed", "released" and scroll values last for one update.
+#[derive(Debug, Default, Clone)]
+pub struct MouseState {
+    position: Option<[f32; 2]>,
+    down: HashSet<MouseButton>,
+    pressed: HashSet<MouseButton>,
+    released: HashSet<MouseButton>,
+    scroll: [f32; 2],
+    motion: [f32; 2],
+    viewport: Viewport,
+}
+
+impl MouseState {
+    pub fn new(viewport: Viewport) -> Self {
+        Self {
+            viewport,
+            ..Self::default()
+        }
+    }
+
+    /// Cursor position in window pixels, `None` if the cursor is outside of the window.
+    pub fn position(&self) -> Option<[f32; 2]> {
+        self.position
+    }
+
+    /// Cursor position in the coordinate space of [`Vertex::position`](crate::game_objects::Vertex).
+    pub fn world_position(&self) -> Option<[f32; 2]> {
+        self.position.map(|position| self.viewport.to_world(position))
+    }
+
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        self.down.contains(&button)
+    }
+
+    pub fn was_pressed(&self, button: MouseButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn was_released(&self, button: MouseButton) -> bool {
+        self.released.contains(&button)
+    }
+
+    /// Scroll in lines since the previous update.
+    pub fn scroll(&self) -> [f32; 2] {
+        self.scroll
+    }
+
+    /// Raw mouse movement since the previous update, e.g. for mouse look.
+    pub fn motion(&self) -> [f32; 2] {
+        self.motion
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    pub fn apply(&mut self, event: &MouseEvent) {
+        match event {
+            MouseEvent::CursorMoved { position } => self.position = Some(*position),
+            MouseEvent::CursorEntered => {}
+            MouseEvent::CursorLeft => self.position = None,
+            MouseEvent::Button { button, state: ButtonState::Pressed } => {
+                if self.down.insert(*button) {
+                    self.pressed.insert(*button);
+                }
+            }
+            MouseEvent::Button { button, state: ButtonState::Released } => {
+                if self.down.remove(button) {
+                    self.released.insert(*button);
+                }
+            }
+            MouseEvent::Wheel { delta } => {
+                let [x, y] = match delta {
+                    ScrollDelta::Lines(lines) => *lines,
+                    ScrollDelta::Pixels([x, y]) => [x / PIXELS_PER_SCROLL_LINE, y / PIXELS_PER_SCROLL_LINE],
+                };
+                self.scroll[0] += x;
+                self.scroll[1] += y;
+            }
+            MouseEvent::Motion { delta } => {
+                self.motion[0] += delta[0];
+                self.motion[1] += delta[1];
+            }
+        }
+    }
+
+    /// Forgets buttons pressed and released, the scroll and the motion during the update, it's called by the engine after every update.
+    pub fn end_update(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.scroll = [0.0, 0.0];
+        self.motion = [0.0, 0.0];
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// Text input which is separate from raw keys, so layouts and input methods are taken into account.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TextEvent {
+    /// Text typed by keys or committed by an input method
+    Commit(String),
+    /// Text an input method is composing, `cursor` is a byte range inside it.
+    /// An empty text means the composition is over
+    Preedit { text: String, cursor: Option<[usize; 2]> },
+}
+
+/// Text input state which is polled by scenes. The committed text lasts for one update.
+#[derive(Debug, Default, Clone)]
+pub struct TextState {
+    text: String,
+    preedit: Option<(String, Option<[usize; 2]>)>,
+}
+
+impl TextState {
+    /// Text committed since the previous update.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Text the input method is composing and the cursor inside it, `None` if nothing is being composed.
+    pub fn preedit(&self) -> Option<(&str, Option<[usize; 2]>)> {
+        self.preedit.as_ref().map(|(text, cursor)| (text.as_str(), *cursor))
+    }
+
+    pub fn apply(&mut self, event: &TextEvent) {
+        match event {
+            TextEvent::Commit(text) => self.text.push_str(text),
+            TextEvent::Preedit { text, .. } if text.is_empty() => self.preedit = None,
+            TextEvent::Preedit { text, cursor } => self.preedit = Some((text.clone(), *cursor)),
+        }
+    }
+
+    /// Forgets the text committed during the update, it's called by the engine after every update.
+    pub fn end_update(&mut self) {
+        self.text.clear();
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::Viewport;
+
+/// A touch which is released before this time without moving is a tap.
+pub const TAP_MAX_DURATION: Duration = Duration::from_millis(300);
+/// A touch which is held for this time without moving is a long press.
+pub const LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
+/// A touch which moves further than this distance in pixels is a pan and can't be a tap or a long press anymore.
+pub const TAP_MAX_DISTANCE: f32 = 10.0;
+
+/// Identifies a finger for as long as it touches the screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TouchId(pub u64);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+/// Touch position in window pixels, the origin is the top left corner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TouchEvent {
+    pub id: TouchId,
+    pub phase: TouchPhase,
+    pub position: [f32; 2],
+}
+
+/// Gestures recognised by [`TouchState`], positions are in window pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Gesture {
+    Tap { position: [f32; 2] },
+    LongPress { position: [f32; 2] },
+    /// Two fingers moved, `scale` is the ratio of the distance between them to the previous one
+    Pinch { center: [f32; 2], scale: f32 },
+    /// A single finger moved by `delta` to `position`
+    Pan { position: [f32; 2], delta: [f32; 2] },
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TouchPoint {
+    pub start_position: [f32; 2],
+    pub position: [f32; 2],
+    pub started_at: Duration,
+    is_moved: bool,
+    is_long_pressed: bool,
+}
+
+/// Touch points and gestures which are polled by scenes. Gestures last for one update.
+/// The state knows only the time passed through [`TouchState::advance`], so gestures stay deterministic like timers.
+#[derive(Debug, Default, Clone)]
+pub struct TouchState {
+    now: Duration,
+    points: HashMap<TouchId, TouchPoint>,
+    gestures: Vec<Gesture>,
+    viewport: Viewport,
+}
+
+impl TouchState {
+    pub fn new(viewport: Viewport) -> Self {
+        Self {
+            viewport,
+            ..Self::default()
+        }
+    }
+
+    pub fn point(&self, id: TouchId) -> Option<&TouchPoint> {
+        self.points.get(&id)
+    }
+
+    /// Fingers which touch the screen right now.
+    pub fn points(&self) -> impl Iterator<Item=(&TouchId, &TouchPoint)> {
+        self.points.iter()
+    }
+
+    /// Gestures recognised since the previous update in the order they were made.
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    /// Maps a touch or gesture position into the coordinate space of [`Vertex::position`](crate::game_objects::Vertex).
+    pub fn to_world(&self, position: [f32; 2]) -> [f32; 2] {
+        self.viewport.to_world(position)
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    /// Moves the gesture time forward, it's called by the engine before every update.
+    pub fn advance(&mut self, delta: Duration) {
+        self.now += delta;
+        for point in self.points.values_mut() {
+            if !point.is_moved && !point.is_long_pressed && self.now - point.started_at >= LONG_PRESS_DURATION {
+                point.is_long_pressed = true;
+                self.gestures.push(Gesture::LongPress { position: point.position });
+            }
+        }
+    }
+
+    pub fn apply(&mut self, event: &TouchEvent) {
+        match event.phase {
+            TouchPhase::Started => {
+                //several fingers make a pinch, not taps or long presses
+                self.points.values_mut().for_each(|point| point.is_moved = true);
+                let is_moved = !self.points.is_empty();
+                self.points.insert(event.id, TouchPoint {
+                    start_position: event.position,
+                    position: event.position,
+                    started_at: self.now,
+                    is_moved,
+                    is_long_pressed: false,
+                });
+            }
+            TouchPhase::Moved => self.move_point(event.id, event.position),
+            TouchPhase::Ended => {
+                if let Some(point) = self.points.remove(&event.id) {
+                    let is_tap = !point.is_moved && !point.is_long_pressed && self.now - point.started_at <= TAP_MAX_DURATION;
+                    if is_tap && self.points.is_empty() {
+                        self.gestures.push(Gesture::Tap { position: point.position });
+                    }
+                }
+            }
+            TouchPhase::Cancelled => {
+                self.points.remove(&event.id);
+            }
+        }
+    }
+
+    /// Forgets gestures recognised during the update, it's called by the engine after every update.
+    pub fn end_update(&mut self) {
+        self.gestures.clear();
+    }
+
+    fn move_point(&mut self, id: TouchId, position: [f32; 2]) {
+        let points_count = self.points.len();
+        let other_position = match points_count {
+            2 => self.points.iter().find(|(other_id, _)| **other_id != id).map(|(_, other)| other.position),
+            _ => None,
+        };
+        let Some(point) = self.points.get_mut(&id) else {
+            return;
+        };
+        let previous_position = point.position;
+        point.position = position;
+        if distance(point.start_position, position) > TAP_MAX_DISTANCE {
+            point.is_moved = true;
+        }
+
+        if let Some(other_position) = other_position {
+            let previous_distance = distance(previous_position, other_position);
+            if previous_distance > 0.0 {
+                self.gestures.push(Gesture::Pinch {
+                    center: [(position[0] + other_position[0]) / 2.0, (position[1] + other_position[1]) / 2.0],
+                    scale: distance(position, other_position) / previous_distance,
+                });
+            }
+        } else if point.is_moved && points_count == 1 {
+            let delta = [position[0] - previous_position[0], position[1] - previous_position[1]];
+            self.gestures.push(Gesture::Pan { position, delta });
+        }
+    }
+}
+
+fn distance(from: [f32; 2], to: [f32; 2]) -> f32 {
+    (to[0] - from[0]).hypot(to[1] - from[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(state: &mut TouchState, id: u64, phase: TouchPhase, position: [f32; 2]) {
+        state.apply(&TouchEvent { id: TouchId(id), phase, position });
+    }
+
+    #[test]
+    fn short_touch_is_a_tap() {
+        let mut state = TouchState::default();
+        touch(&mut state, 1, TouchPhase::Started, [10.0, 10.0]);
+        state.advance(Duration::from_millis(100));
+        touch(&mut state, 1, TouchPhase::Moved, [15.0, 12.0]);
+        touch(&mut state, 1, TouchPhase::Ended, [15.0, 12.0]);
+
+        assert_eq!(state.gestures(), &[Gesture::Tap { position: [15.0, 12.0] }]);
+        state.end_update();
+        assert!(state.gestures().is_empty());
+        assert_eq!(state.points().count(), 0);
+    }
+
+    #[test]
+    fn slow_or_cancelled_touch_is_not_a_tap() {
+        let mut state = TouchState::default();
+        touch(&mut state, 1, TouchPhase::Started, [10.0, 10.0]);
+        state.advance(TAP_MAX_DURATION + Duration::from_millis(1));
+        touch(&mut state, 1, TouchPhase::Ended, [10.0, 10.0]);
+
+        touch(&mut state, 2, TouchPhase::Started, [10.0, 10.0]);
+        touch(&mut state, 2, TouchPhase::Cancelled, [10.0, 10.0]);
+        assert!(state.gestures().is_empty());
+    }
+
+    #[test]
+    fn held_touch_is_a_long_press_once() {
+        let mut state = TouchState::default();
+        touch(&mut state, 1, TouchPhase::Started, [40.0, 30.0]);
+        state.advance(LONG_PRESS_DURATION - Duration::from_millis(1));
+        assert!(state.gestures().is_empty());
+
+        state.advance(Duration::from_millis(1));
+        assert_eq!(state.gestures(), &[Gesture::LongPress { position: [40.0, 30.0] }]);
+        state.end_update();
+
+        //it's reported once and the release isn't a tap
+        state.advance(LONG_PRESS_DURATION);
+        touch(&mut state, 1, TouchPhase::Ended, [40.0, 30.0]);
+        assert!(state.gestures().is_empty());
+    }
+
+    #[test]
+    fn moved_touch_is_a_pan() {
+        let mut state = TouchState::default();
+        touch(&mut state, 1, TouchPhase::Started, [0.0, 0.0]);
+        //small moves are still a tap candidate
+        touch(&mut state, 1, TouchPhase::Moved, [5.0, 0.0]);
+        assert!(state.gestures().is_empty());
+
+        touch(&mut state, 1, TouchPhase::Moved, [20.0, 0.0]);
+        touch(&mut state, 1, TouchPhase::Moved, [20.0, 8.0]);
+        assert_eq!(state.gestures(), &[
+            Gesture::Pan { position: [20.0, 0.0], delta: [15.0, 0.0] },
+            Gesture::Pan { position: [20.0, 8.0], delta: [0.0, 8.0] },
+        ]);
+        state.end_update();
+
+        //a moved touch can't become a long press or a tap
+        state.advance(LONG_PRESS_DURATION);
+        touch(&mut state, 1, TouchPhase::Ended, [20.0, 8.0]);
+        assert!(state.gestures().is_empty());
+    }
+
+    #[test]
+    fn two_fingers_pinch() {
+        let mut state = TouchState::default();
+        touch(&mut state, 1, TouchPhase::Started, [0.0, 0.0]);
+        touch(&mut state, 2, TouchPhase::Started, [100.0, 0.0]);
+        touch(&mut state, 2, TouchPhase::Moved, [200.0, 0.0]);
+        touch(&mut state, 1, TouchPhase::Moved, [100.0, 0.0]);
+
+        assert_eq!(state.gestures(), &[
+            Gesture::Pinch { center: [100.0, 0.0], scale: 2.0 },
+            Gesture::Pinch { center: [150.0, 0.0], scale: 0.5 },
+        ]);
+        state.end_update();
+
+        //the finger left after the pinch pans, but nothing is a tap or a long press
+        state.advance(LONG_PRESS_DURATION);
+        touch(&mut state, 2, TouchPhase::Ended, [200.0, 0.0]);
+        touch(&mut state, 1, TouchPhase::Moved, [150.0, 0.0]);
+        touch(&mut state, 1, TouchPhase::Ended, [150.0, 0.0]);
+        assert_eq!(state.gestures(), &[Gesture::Pan { position: [150.0, 0.0], delta: [50.0, 0.0] }]);
+    }
+}
@@ -1,26 +1,81 @@
 use error_stack::Result;
+use serde::{Deserialize, Serialize};
 
 use crate::error::ThrustlerError;
 use crate::game_objects::Scene;
+use crate::input::InputEvent;
+use crate::shader::Shader;
+use crate::stats::RenderStats;
+use crate::window::{WindowControl, WindowId};
 
+pub mod clipboard;
+pub mod context;
 pub mod error;
 pub mod game_objects;
+pub mod input;
+pub mod lifecycle;
+pub mod render;
+pub mod scheduler;
+pub mod shader;
+pub mod stats;
+pub mod time;
+pub mod window;
+
+/// Handles events of the window loop, an error stops the loop.
+pub type EventDispatcher = Box<dyn FnMut(WindowEvent) -> Result<(), ThrustlerError>>;
 
 pub trait ThrustlerWindow {
-    fn start(&self, dispatcher: Box<dyn FnMut(WindowEvent) -> ()>) -> Result<(), ThrustlerError>;
+    /// Runs the window loop until the window is closed or the dispatcher returns an error,
+    /// the error is returned from this method then.
+    /// Commands of `window_control` are applied after every dispatched event.
+    fn start(
+        &self,
+        dispatcher: EventDispatcher,
+        window_control: WindowControl,
+    ) -> Result<(), ThrustlerError>;
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum WindowEvent {
     OnStart,
+    /// The next frame of the game, it comes once per loop iteration and advances scenes, windows are drawn by [`WindowEvent::Redraw`]
     OnDraw,
     OnStop,
+    /// A window is ready to show a new frame, every window is redrawn on its own, so hidden windows don't hold others back
+    Redraw(WindowId),
+    /// A window other than the primary one is closed, closing the primary window stops the engine
+    WindowClosed(WindowId),
+    /// The drawable area of a window has a new size in physical pixels
+    Resized(WindowId, Size),
+    ScaleFactorChanged(WindowId, f64),
+    /// The application is sent to background, surfaces have to be released
+    Suspended,
+    /// The application is back, backends of all windows are initialized again before this event
+    Resumed,
+    Focused(WindowId, bool),
+    /// The window is minimized or covered by other windows, or it's visible again
+    Occluded(WindowId, bool),
+    Input(WindowId, InputEvent),
 }
 
 pub trait ThrustlerBackend {
-    fn draw_scene(&mut self, scene: &Box<dyn Scene>);
+    /// Draws scenes of a window into a single frame in their order, the frame is cleared and presented once.
+    fn draw_scenes(&mut self, scenes: &[&dyn Scene]) -> Result<(), ThrustlerError>;
+    fn render_stats(&self) -> RenderStats;
+    /// Adapts the surface to the new window size in physical pixels, the size is never zero.
+    fn resize(&mut self, size: Size) -> Result<(), ThrustlerError>;
+    /// Drops the surface and everything created for it when the application is suspended,
+    /// the window initializes the backend again when it's resumed.
+    fn release_surface(&mut self);
+    /// True if the y axis of the clip space points up, it's used to map the cursor into the scene coordinates.
+    fn is_y_axis_up(&self) -> bool;
+    /// Rebuilds pipelines of the shader from its new source, [`ShaderId::BUILT_IN`](crate::shader::ShaderId::BUILT_IN) replaces the built-in ones.
+    /// The previous pipelines are kept if the source can't be compiled.
+    fn reload_shader(&mut self, shader: &Shader) -> Result<(), ThrustlerError>;
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Size in physical pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Size {
     pub width: u32,
     pub height: u32,
@@ -36,6 +91,37 @@ impl Size {
     pub fn new(width: u32, height: u32) -> Self {
         Size { width, height }
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn to_logical(&self, scale_factor: f64) -> LogicalSize {
+        LogicalSize::new(self.width as f64 / scale_factor, self.height as f64 / scale_factor)
+    }
+}
+
+/// Size in logical pixels, the scale factor of a monitor turns them into physical ones.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LogicalSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for LogicalSize {
+    fn default() -> Self {
+        LogicalSize::new(800.0, 600.0)
+    }
+}
+
+impl LogicalSize {
+    pub fn new(width: f64, height: f64) -> Self {
+        LogicalSize { width, height }
+    }
+
+    pub fn to_physical(&self, scale_factor: f64) -> Size {
+        Size::new((self.width * scale_factor).round() as u32, (self.height * scale_factor).round() as u32)
+    }
 }
 
 impl Into<[u32; 2]> for Size {
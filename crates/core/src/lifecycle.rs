@@ -0,0 +1,14 @@
+/// Changes of the application or window state which are delivered to
+/// [`Scene::on_lifecycle`](crate::game_objects::Scene::on_lifecycle).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// The application is sent to background, updates are paused and surfaces are released
+    Suspended,
+    /// The application is back, surfaces are recreated
+    Resumed,
+    FocusGained,
+    FocusLost,
+    /// The window is minimized or covered by other windows, it isn't drawn then
+    Hidden,
+    Shown,
+}
@@ -0,0 +1,128 @@
+use std::env;
+
+/// The environment variable which overrides [`RenderSettings::adapter`],
+/// it takes `discrete`, `integrated`, `software` or a part of the adapter name.
+pub const ADAPTER_ENV_VAR: &str = "THRUSTLER_ADAPTER";
+
+/// Which GPU the backends use if there are several of them.
+/// Other adapters are still used if the preferred one doesn't exist or can't draw into the window.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AdapterPreference {
+    #[default]
+    Discrete,
+    Integrated,
+    /// A CPU implementation, e.g. for machines without a GPU
+    Software,
+    /// An adapter whose name contains this text, the case doesn't matter
+    Name(String),
+}
+
+/// Kind of a graphics adapter reported by the driver.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AdapterKind {
+    Discrete,
+    Integrated,
+    Virtual,
+    Software,
+    Other,
+}
+
+impl AdapterPreference {
+    /// The preference from [`ADAPTER_ENV_VAR`], `None` if it isn't set.
+    pub fn from_env() -> Option<AdapterPreference> {
+        let value = env::var(ADAPTER_ENV_VAR).ok()?;
+        let preference = match value.trim().to_lowercase().as_str() {
+            "" => return None,
+            "discrete" => AdapterPreference::Discrete,
+            "integrated" => AdapterPreference::Integrated,
+            "software" | "cpu" => AdapterPreference::Software,
+            _ => AdapterPreference::Name(value.trim().to_string()),
+        };
+        Some(preference)
+    }
+
+    /// Orders adapters by this preference, the adapter with the lowest rank is chosen.
+    pub fn rank(&self, name: &str, kind: AdapterKind) -> u32 {
+        let is_preferred = match self {
+            AdapterPreference::Discrete => kind == AdapterKind::Discrete,
+            AdapterPreference::Integrated => kind == AdapterKind::Integrated,
+            AdapterPreference::Software => kind == AdapterKind::Software,
+            AdapterPreference::Name(part) => name.to_lowercase().contains(&part.to_lowercase()),
+        };
+        //the rest go from the fastest usually
+        let kind_rank = match kind {
+            AdapterKind::Discrete => 1,
+            AdapterKind::Integrated => 2,
+            AdapterKind::Virtual => 3,
+            AdapterKind::Software => 4,
+            AdapterKind::Other => 5,
+        };
+        if is_preferred { 0 } else { kind_rank }
+    }
+}
+
+/// How finished frames are shown, i.e. whether the frame rate is synchronized with the display.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PresentMode {
+    /// Vsync, frames wait for the vertical blank. It's supported everywhere
+    #[default]
+    Fifo,
+    /// Vsync, but a late frame is shown right away and may tear
+    FifoRelaxed,
+    /// The newest frame replaces the waiting one, so the frame rate isn't capped and nothing tears
+    Mailbox,
+    /// Frames are shown right away, the frame rate isn't capped and frames may tear
+    Immediate,
+}
+
+impl PresentMode {
+    /// Picks this mode or the closest supported one, `Fifo` is taken if nothing else is supported.
+    pub fn choose(self, is_supported: impl Fn(PresentMode) -> bool) -> PresentMode {
+        let candidates: &[PresentMode] = match self {
+            PresentMode::Fifo => &[],
+            PresentMode::FifoRelaxed => &[PresentMode::FifoRelaxed],
+            PresentMode::Mailbox => &[PresentMode::Mailbox],
+            //both keep the frame rate uncapped
+            PresentMode::Immediate => &[PresentMode::Immediate, PresentMode::Mailbox],
+        };
+        candidates.iter()
+            .copied()
+            .find(|present_mode| is_supported(*present_mode))
+            .unwrap_or(PresentMode::Fifo)
+    }
+}
+
+/// Settings of the graphical backends, they are the same for all windows.
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub adapter: AdapterPreference,
+    /// The mode is replaced by a supported one if the surface doesn't support it
+    pub present_mode: PresentMode,
+    /// How many frames the CPU may prepare before the GPU finishes the oldest one, at least 1
+    pub frames_in_flight: u32,
+    /// MSAA samples per pixel, 1 disables anti-aliasing. The highest supported count below it is used if it isn't supported
+    pub sample_count: u32,
+}
+
+impl RenderSettings {
+    /// The requested sample count or the highest supported power of two below it, 1 is always supported.
+    pub fn supported_sample_count(&self, is_supported: impl Fn(u32) -> bool) -> u32 {
+        //sample counts are powers of two
+        let mut sample_count = 1 << self.sample_count.max(1).ilog2();
+        while sample_count > 1 && !is_supported(sample_count) {
+            sample_count /= 2;
+        }
+        sample_count
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            adapter: AdapterPreference::default(),
+            present_mode: PresentMode::default(),
+            frames_in_flight: 2,
+            sample_count: 1,
+        }
+    }
+}
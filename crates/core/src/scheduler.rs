@@ -0,0 +1,344 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+/// Delivered to [`Scene::on_timer`](crate::game_objects::Scene::on_timer) when a timer fires
+/// or a sequence reaches one of its signals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerEvent {
+    pub id: TimerId,
+    pub name: Cow<'static, str>,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Wait(Duration),
+    Signal(Cow<'static, str>),
+}
+
+/// A list of waits and signals which is played step by step by the [`Scheduler`],
+/// like a coroutine which yields on every wait.
+#[derive(Debug, Clone, Default)]
+pub struct Sequence {
+    steps: Vec<Step>,
+    looped: bool,
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wait(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Wait(duration));
+        self
+    }
+
+    pub fn signal(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.steps.push(Step::Signal(name.into()));
+        self
+    }
+
+    /// Appends the steps of another sequence, so sequences can be built out of reusable parts.
+    pub fn then(mut self, sequence: Sequence) -> Self {
+        self.steps.extend(sequence.steps);
+        self
+    }
+
+    /// Starts the sequence over when the last step is done.
+    /// It is ignored for sequences without non-zero waits, they would never yield otherwise.
+    pub fn looped(mut self) -> Self {
+        self.looped = true;
+        self
+    }
+
+    fn can_loop(&self) -> bool {
+        self.looped && self.steps.iter().any(|step| matches!(step, Step::Wait(duration) if !duration.is_zero()))
+    }
+}
+
+#[derive(Debug)]
+enum TimerKind {
+    Once(Cow<'static, str>),
+    Repeat(Cow<'static, str>, Duration),
+    Sequence(Sequence, usize),
+}
+
+#[derive(Debug)]
+struct Timer {
+    id: TimerId,
+    fire_at: Duration,
+    kind: TimerKind,
+}
+
+/// Timers of a single scene. The scheduler knows only the time passed through [`Scheduler::advance`],
+/// so timers are driven by the fixed timestep of the engine and stay deterministic.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    now: Duration,
+    next_id: u64,
+    timers: Vec<Timer>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fires `name` once after `delay`, names can be literals or built at runtime.
+    pub fn after(&mut self, delay: Duration, name: impl Into<Cow<'static, str>>) -> TimerId {
+        self.add_timer(delay, TimerKind::Once(name.into()))
+    }
+
+    /// Fires `name` every `interval` until cancelled. A zero interval fires once per update.
+    pub fn every(&mut self, interval: Duration, name: impl Into<Cow<'static, str>>) -> TimerId {
+        self.add_timer(interval, TimerKind::Repeat(name.into(), interval))
+    }
+
+    /// Starts playing the sequence from the current update.
+    pub fn run(&mut self, sequence: Sequence) -> TimerId {
+        self.add_timer(Duration::ZERO, TimerKind::Sequence(sequence, 0))
+    }
+
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let timers_count = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        timers_count != self.timers.len()
+    }
+
+    pub fn is_active(&self, id: TimerId) -> bool {
+        self.timers.iter().any(|timer| timer.id == id)
+    }
+
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+
+    /// Time passed through the scheduler since its creation.
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Moves the scheduler time forward and returns the fired events in the order they were due.
+    pub fn advance(&mut self, delta: Duration) -> Vec<TimerEvent> {
+        self.now += delta;
+        let mut events = vec![];
+
+        while let Some(index) = self.next_due_timer() {
+            let timer = &mut self.timers[index];
+            let is_finished = match &mut timer.kind {
+                TimerKind::Once(name) => {
+                    events.push(TimerEvent { id: timer.id, name: name.clone() });
+                    true
+                }
+                TimerKind::Repeat(name, interval) => {
+                    events.push(TimerEvent { id: timer.id, name: name.clone() });
+                    timer.fire_at = if interval.is_zero() {
+                        self.now + Duration::from_nanos(1)
+                    } else {
+                        timer.fire_at + *interval
+                    };
+                    false
+                }
+                TimerKind::Sequence(sequence, step_index) => {
+                    Self::play_sequence(timer.id, &mut timer.fire_at, sequence, step_index, &mut events)
+                }
+            };
+
+            if is_finished {
+                self.timers.remove(index);
+            }
+        }
+        events
+    }
+
+    fn add_timer(&mut self, delay: Duration, kind: TimerKind) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            fire_at: self.now + delay,
+            kind,
+        });
+        id
+    }
+
+    fn next_due_timer(&self) -> Option<usize> {
+        self.timers.iter()
+            .enumerate()
+            .filter(|(_, timer)| timer.fire_at <= self.now)
+            .min_by_key(|(_, timer)| (timer.fire_at, timer.id.0))
+            .map(|(index, _)| index)
+    }
+
+    /// Plays steps until the next non-zero wait, returns true when the sequence is over.
+    fn play_sequence(
+        id: TimerId,
+        fire_at: &mut Duration,
+        sequence: &Sequence,
+        step_index: &mut usize,
+        events: &mut Vec<TimerEvent>,
+    ) -> bool {
+        loop {
+            if *step_index >= sequence.steps.len() {
+                if !sequence.can_loop() {
+                    return true;
+                }
+                *step_index = 0;
+            }
+
+            let step = &sequence.steps[*step_index];
+            *step_index += 1;
+            match step {
+                Step::Signal(name) => events.push(TimerEvent { id, name: name.clone() }),
+                Step::Wait(duration) if duration.is_zero() => {}
+                Step::Wait(duration) => {
+                    *fire_at += *duration;
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn names(events: Vec<TimerEvent>) -> Vec<String> {
+        events.into_iter().map(|event| event.name.into_owned()).collect()
+    }
+
+    #[test]
+    fn once_timer_fires_after_delay() {
+        let mut scheduler = Scheduler::new();
+        let id = scheduler.after(ms(100), "once");
+
+        assert!(scheduler.advance(ms(99)).is_empty());
+        assert_eq!(scheduler.advance(ms(1)), vec![TimerEvent { id, name: "once".into() }]);
+        assert!(!scheduler.is_active(id));
+        assert!(scheduler.advance(ms(1000)).is_empty());
+        assert_eq!(scheduler.now(), ms(1100));
+    }
+
+    #[test]
+    fn repeat_timer_catches_up_with_long_updates() {
+        let mut scheduler = Scheduler::new();
+        let id = scheduler.every(ms(10), "tick");
+
+        assert_eq!(names(scheduler.advance(ms(35))), vec!["tick"; 3]);
+        assert!(scheduler.advance(ms(4)).is_empty());
+        assert_eq!(names(scheduler.advance(ms(1))), vec!["tick"]);
+        assert!(scheduler.is_active(id));
+    }
+
+    #[test]
+    fn zero_interval_fires_once_per_update() {
+        let mut scheduler = Scheduler::new();
+        scheduler.every(Duration::ZERO, "update");
+
+        assert_eq!(names(scheduler.advance(ms(16))), vec!["update"]);
+        assert_eq!(names(scheduler.advance(ms(16))), vec!["update"]);
+        //the time doesn't move, so it isn't the next update for the timer
+        assert!(scheduler.advance(Duration::ZERO).is_empty());
+    }
+
+    #[test]
+    fn zero_delay_fires_in_the_current_update() {
+        let mut scheduler = Scheduler::new();
+        scheduler.after(Duration::ZERO, "now");
+
+        assert_eq!(names(scheduler.advance(Duration::ZERO)), vec!["now"]);
+    }
+
+    #[test]
+    fn sequence_waits_between_signals() {
+        let mut scheduler = Scheduler::new();
+        let id = scheduler.run(Sequence::new()
+            .signal("start")
+            .wait(ms(100))
+            .signal("middle")
+            .wait(Duration::ZERO)
+            .signal("right after")
+            .wait(ms(50))
+            .signal("end"));
+
+        assert_eq!(names(scheduler.advance(Duration::ZERO)), vec!["start"]);
+        assert!(scheduler.advance(ms(99)).is_empty());
+        assert_eq!(names(scheduler.advance(ms(1))), vec!["middle", "right after"]);
+        assert_eq!(names(scheduler.advance(ms(50))), vec!["end"]);
+        assert!(!scheduler.is_active(id));
+    }
+
+    #[test]
+    fn names_can_be_built_at_runtime() {
+        let mut scheduler = Scheduler::new();
+        let wave = |index: u32| Sequence::new().signal(format!("wave {index}")).wait(ms(10));
+        scheduler.run(wave(1).then(wave(2)).signal("done"));
+        scheduler.after(ms(15), String::from("boss"));
+
+        assert_eq!(names(scheduler.advance(Duration::ZERO)), vec!["wave 1"]);
+        assert_eq!(names(scheduler.advance(ms(10))), vec!["wave 2"]);
+        assert_eq!(names(scheduler.advance(ms(10))), vec!["boss", "done"]);
+    }
+
+    #[test]
+    fn looped_sequence_starts_over() {
+        let mut scheduler = Scheduler::new();
+        let id = scheduler.run(Sequence::new().wait(ms(10)).signal("a").wait(ms(5)).signal("b").looped());
+
+        assert_eq!(names(scheduler.advance(ms(31))), vec!["a", "b", "a", "b"]);
+        assert_eq!(names(scheduler.advance(ms(9))), vec!["a"]);
+        assert!(scheduler.is_active(id));
+    }
+
+    #[test]
+    fn looped_sequence_without_waits_plays_once() {
+        let mut scheduler = Scheduler::new();
+        let id = scheduler.run(Sequence::new().signal("a").wait(Duration::ZERO).looped());
+
+        assert_eq!(names(scheduler.advance(ms(10))), vec!["a"]);
+        assert!(!scheduler.is_active(id));
+    }
+
+    #[test]
+    fn cancelled_timers_do_not_fire() {
+        let mut scheduler = Scheduler::new();
+        let once = scheduler.after(ms(10), "once");
+        let repeat = scheduler.every(ms(10), "repeat");
+        scheduler.after(ms(10), "kept");
+
+        assert!(scheduler.cancel(once));
+        assert!(!scheduler.cancel(once));
+        assert_eq!(names(scheduler.advance(ms(10))), vec!["repeat", "kept"]);
+
+        assert!(scheduler.cancel(repeat));
+        assert!(scheduler.advance(ms(100)).is_empty());
+
+        scheduler.every(ms(10), "cleared");
+        scheduler.clear();
+        assert!(scheduler.advance(ms(100)).is_empty());
+    }
+
+    #[test]
+    fn timers_fire_in_due_order_within_one_update() {
+        let mut scheduler = Scheduler::new();
+        scheduler.after(ms(30), "late");
+        scheduler.every(ms(20), "repeat");
+        scheduler.after(ms(10), "early");
+        scheduler.run(Sequence::new().wait(ms(25)).signal("sequence"));
+        //timers due at the same time fire in the order they were added
+        scheduler.after(ms(20), "same time");
+
+        assert_eq!(
+            names(scheduler.advance(ms(40))),
+            vec!["early", "repeat", "same time", "sequence", "late", "repeat"],
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, Result, ResultExt};
+use uuid::Uuid;
+
+use crate::error::ThrustlerError;
+
+/// Shader files of the project, [`ShaderFiles::in_assets`] looks for them here.
+pub const SHADERS_DIR: &str = "assets/shaders";
+
+/// Identifies a shader, backends cache pipelines by it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ShaderId(Uuid);
+
+impl ShaderId {
+    /// The shader which backends use for game objects without their own shader.
+    pub const BUILT_IN: ShaderId = ShaderId(Uuid::nil());
+}
+
+/// Files a shader is loaded from, relative paths start from the working directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderFiles {
+    pub wgsl: PathBuf,
+}
+
+impl ShaderFiles {
+    /// `wgsl/{name}.wgsl` in [`SHADERS_DIR`].
+    pub fn in_assets(name: &str) -> Self {
+        Self {
+            wgsl: wgsl_dir().join(format!("{name}.wgsl")),
+        }
+    }
+
+    /// Files of the built-in shader, backends compile it in and load the files only to reload it.
+    pub fn built_in() -> Self {
+        Self::in_assets("simple_shader")
+    }
+
+    /// The directory which [`ShaderFiles::in_assets`] takes WGSL files from.
+    pub fn wgsl_dir() -> PathBuf {
+        wgsl_dir()
+    }
+}
+
+/// WGSL vertex and fragment shaders supplied at runtime, they are assigned to game objects with [`GameObject::with_shader`](crate::game_objects::GameObject::with_shader).
+/// Both backends take the same source with the `vs_main` and `fs_main` entry points, Vulkan compiles it into SPIR-V with naga.
+/// The source is compiled when an object with the shader is drawn for the first time, the object is drawn with the built-in shader if it fails.
+/// The vertex position comes into the vertex stage as `vec2<f32>` at location 0.
+///
+/// The y axis of the clip space isn't flipped for Vulkan, so it points up with wgpu and down with Vulkan,
+/// and the same source draws the image upside down on Vulkan. Positions which have to look the same with both backends
+/// are flipped by the game, [`ThrustlerBackend::is_y_axis_up`](crate::ThrustlerBackend::is_y_axis_up) tells which way the axis points.
+#[derive(Debug, Clone)]
+pub struct Shader {
+    id: ShaderId,
+    wgsl: String,
+    files: Option<ShaderFiles>,
+}
+
+impl Shader {
+    /// Loads the shader from its files.
+    pub fn load(files: ShaderFiles) -> Result<Shader, ThrustlerError> {
+        Self::load_with_id(ShaderId(Uuid::new_v4()), files)
+    }
+
+    /// Loads the shader `name` from [`SHADERS_DIR`], see [`ShaderFiles::in_assets`].
+    pub fn from_assets(name: &str) -> Result<Shader, ThrustlerError> {
+        Self::load(ShaderFiles::in_assets(name))
+    }
+
+    /// The built-in shader loaded from its files, backends replace their built-in pipelines with it.
+    pub fn built_in() -> Result<Shader, ThrustlerError> {
+        Self::load_with_id(ShaderId::BUILT_IN, ShaderFiles::built_in())
+    }
+
+    /// Loads the files of the shader again, the id is kept, so backends replace its pipelines.
+    pub fn reload(&self) -> Result<Shader, ThrustlerError> {
+        let files = self.files.clone()
+            .ok_or(Report::new(ThrustlerError::ShaderError))
+            .attach_printable("The shader isn't loaded from files")?;
+        Self::load_with_id(self.id, files)
+    }
+
+    pub fn from_wgsl(source: impl Into<String>) -> Self {
+        Self {
+            id: ShaderId(Uuid::new_v4()),
+            wgsl: source.into(),
+            files: None,
+        }
+    }
+
+    pub fn id(&self) -> ShaderId {
+        self.id
+    }
+
+    pub fn wgsl(&self) -> &str {
+        &self.wgsl
+    }
+
+    /// Files the shader is loaded from, `None` if it's made of a string.
+    pub fn files(&self) -> Option<&ShaderFiles> {
+        self.files.as_ref()
+    }
+
+    fn load_with_id(id: ShaderId, files: ShaderFiles) -> Result<Shader, ThrustlerError> {
+        let wgsl = fs::read_to_string(&files.wgsl)
+            .attach_printable_lazy(|| format!("Can't read the shader {}", files.wgsl.display()))
+            .change_context(ThrustlerError::ShaderError)?;
+        Ok(Self {
+            id,
+            wgsl,
+            files: Some(files),
+        })
+    }
+}
+
+fn wgsl_dir() -> PathBuf {
+    Path::new(SHADERS_DIR).join("wgsl")
+}
@@ -0,0 +1,81 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Statistics reported by a graphical backend for the last drawn scene.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub cached_buffers: usize,
+    pub uploaded_bytes: u64,
+}
+
+/// Upper bounds of histogram buckets in milliseconds, the last bucket takes everything above.
+pub const FRAME_TIME_BUCKETS_MS: [u64; 5] = [4, 8, 16, 33, 66];
+
+#[derive(Debug, Default, Clone)]
+pub struct FrameTimeHistogram {
+    buckets: [u64; FRAME_TIME_BUCKETS_MS.len() + 1],
+}
+
+impl FrameTimeHistogram {
+    pub fn add(&mut self, frame_time: Duration) {
+        let millis = frame_time.as_millis() as u64;
+        let index = FRAME_TIME_BUCKETS_MS.iter()
+            .position(|upper_bound| millis < *upper_bound)
+            .unwrap_or(FRAME_TIME_BUCKETS_MS.len());
+        self.buckets[index] += 1;
+    }
+
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets = Default::default();
+    }
+}
+
+impl Display for FrameTimeHistogram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut lower_bound = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            match FRAME_TIME_BUCKETS_MS.get(index) {
+                Some(upper_bound) => write!(f, "[{lower_bound}-{upper_bound}ms: {count}] ")?,
+                None => write!(f, "[{lower_bound}ms+: {count}]")?,
+            }
+            lower_bound = FRAME_TIME_BUCKETS_MS.get(index).copied().unwrap_or(lower_bound);
+        }
+        Ok(())
+    }
+}
+
+/// Statistics of the game loop, collected by the engine and available to scenes.
+#[derive(Debug, Default, Clone)]
+pub struct FrameStats {
+    pub frames_per_second: f32,
+    pub frame_count: u64,
+    /// Wall time between the last two rendered frames
+    pub frame_time: Duration,
+    pub update_time: Duration,
+    pub render_time: Duration,
+    pub render: RenderStats,
+    pub total_uploaded_bytes: u64,
+    pub frame_time_histogram: FrameTimeHistogram,
+}
+
+impl Display for FrameStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "fps: {:.1}, frame: {:?}, update: {:?}, render: {:?}, draw calls: {}, cached buffers: {}, uploaded: {} bytes, frame times: {}",
+            self.frames_per_second,
+            self.frame_time,
+            self.update_time,
+            self.render_time,
+            self.render.draw_calls,
+            self.render.cached_buffers,
+            self.total_uploaded_bytes,
+            self.frame_time_histogram,
+        )
+    }
+}
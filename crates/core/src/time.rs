@@ -0,0 +1,145 @@
+/// Controls how the engine advances the simulation time of scenes.
+/// Scenes are still drawn while the game loop is paused.
+#[derive(Debug, Clone)]
+pub struct TimeControl {
+    paused: bool,
+    time_scale: f32,
+    pending_steps: u32,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            time_scale: 1.0,
+            pending_steps: 0,
+        }
+    }
+}
+
+impl TimeControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.pending_steps = 0;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Scales the time passed to the fixed timestep, values below 1.0 give slow motion
+    /// and values above it fast forward. Negative and NaN values are ignored.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        if time_scale >= 0.0 {
+            self.time_scale = time_scale;
+        }
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Requests exactly one fixed update on the next frame, it is ignored if the loop isn't paused.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    /// Consumes a requested step, it's used by the engine.
+    pub fn take_step(&mut self) -> bool {
+        if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_and_resume() {
+        let mut time_control = TimeControl::new();
+        assert!(!time_control.is_paused());
+
+        time_control.pause();
+        assert!(time_control.is_paused());
+        time_control.pause();
+        assert!(time_control.is_paused());
+
+        time_control.resume();
+        assert!(!time_control.is_paused());
+    }
+
+    #[test]
+    fn steps_are_taken_once_while_paused() {
+        let mut time_control = TimeControl::new();
+        time_control.pause();
+        time_control.step();
+        time_control.step();
+
+        assert!(time_control.take_step());
+        assert!(time_control.take_step());
+        assert!(!time_control.take_step());
+        assert!(time_control.is_paused());
+    }
+
+    #[test]
+    fn steps_are_ignored_while_running() {
+        let mut time_control = TimeControl::new();
+        time_control.step();
+        assert!(!time_control.take_step());
+    }
+
+    #[test]
+    fn resume_drops_pending_steps() {
+        let mut time_control = TimeControl::new();
+        time_control.pause();
+        time_control.step();
+        time_control.resume();
+        time_control.pause();
+
+        assert!(!time_control.take_step());
+    }
+
+    #[test]
+    fn time_scale_accepts_zero_and_fast_forward() {
+        let mut time_control = TimeControl::new();
+        assert_eq!(time_control.time_scale(), 1.0);
+
+        time_control.set_time_scale(0.0);
+        assert_eq!(time_control.time_scale(), 0.0);
+        //a zero scale stops the time without pausing, so steps are still ignored
+        assert!(!time_control.is_paused());
+        time_control.step();
+        assert!(!time_control.take_step());
+
+        time_control.set_time_scale(2.5);
+        assert_eq!(time_control.time_scale(), 2.5);
+    }
+
+    #[test]
+    fn time_scale_ignores_negative_and_nan() {
+        let mut time_control = TimeControl::new();
+        time_control.set_time_scale(0.5);
+
+        time_control.set_time_scale(-1.0);
+        assert_eq!(time_control.time_scale(), 0.5);
+        time_control.set_time_scale(f32::NAN);
+        assert_eq!(time_control.time_scale(), 0.5);
+        time_control.set_time_scale(-0.0);
+        assert_eq!(time_control.time_scale(), 0.0);
+    }
+}
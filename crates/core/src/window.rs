@@ -0,0 +1,178 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use serde::{Deserialize, Serialize};
+
+use crate::{LogicalSize, Size};
+
+/// Identifies a window of the engine, the window configured by the engine settings is [`WindowId::PRIMARY`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WindowId(pub u32);
+
+impl WindowId {
+    pub const PRIMARY: WindowId = WindowId(0);
+}
+
+/// The native window which backends draw into, any window with raw handles is one, e.g. a winit or an SDL window.
+pub trait OutputWindow: HasWindowHandle + HasDisplayHandle + Send + Sync {}
+
+impl<T: HasWindowHandle + HasDisplayHandle + Send + Sync> OutputWindow for T {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Fullscreen {
+    /// Covers the current monitor without changing its video mode
+    Borderless,
+    /// Switches the current monitor into the video mode closest to the window size
+    Exclusive,
+}
+
+/// Window icon as raw RGBA pixels, row by row.
+#[derive(Debug, Clone)]
+pub struct WindowIcon {
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Window options which are applied when the window is created.
+#[derive(Debug, Clone)]
+pub struct WindowSettings {
+    pub title: String,
+    pub resizable: bool,
+    pub fullscreen: Option<Fullscreen>,
+    pub decorations: bool,
+    pub icon: Option<WindowIcon>,
+    pub min_size: Option<LogicalSize>,
+    pub max_size: Option<LogicalSize>,
+    /// Position of the top left corner in screen pixels, `None` lets the system place the window
+    pub position: Option<[i32; 2]>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        WindowSettings {
+            title: "Thrustler".to_string(),
+            resizable: true,
+            fullscreen: None,
+            decorations: true,
+            icon: None,
+            min_size: None,
+            max_size: None,
+            position: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CursorGrab {
+    None,
+    /// Keeps the cursor inside the window, e.g. for edge scrolling
+    Confined,
+    /// Keeps the cursor in place, its movement comes as [`MouseEvent::Motion`](crate::input::mouse::MouseEvent::Motion)
+    Locked,
+}
+
+/// Cursor image as raw RGBA pixels, row by row. The hotspot is the clicking point counted from the top left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorImage {
+    pub rgba: Vec<u8>,
+    pub width: u16,
+    pub height: u16,
+    pub hotspot: [u16; 2],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cursor {
+    Default,
+    Custom(CursorImage),
+}
+
+/// A window which has to be opened when the engine starts.
+#[derive(Debug, Clone)]
+pub struct WindowDescriptor {
+    pub id: WindowId,
+    pub size: LogicalSize,
+    pub settings: WindowSettings,
+}
+
+/// The current size and scale factor of a window.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WindowMetrics {
+    /// Size of the drawable area in physical pixels
+    pub size: Size,
+    pub scale_factor: f64,
+}
+
+impl WindowMetrics {
+    pub fn logical_size(&self) -> LogicalSize {
+        self.size.to_logical(self.scale_factor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowCommand {
+    SetTitle(String),
+    SetCursorVisible(bool),
+    SetCursorGrab(CursorGrab),
+    SetCursor(Cursor),
+    SetImeAllowed(bool),
+}
+
+/// Changes of a window requested by scenes at runtime.
+/// The window applies them after the event during which they were requested is dispatched.
+#[derive(Debug, Clone, Default)]
+pub struct WindowControl {
+    window_id: WindowId,
+    commands: Rc<RefCell<Vec<(WindowId, WindowCommand)>>>,
+}
+
+impl WindowControl {
+    /// Controls the primary window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Controls another window, commands of both controls go into the same queue.
+    pub fn for_window(&self, window_id: WindowId) -> WindowControl {
+        Self {
+            window_id,
+            commands: self.commands.clone(),
+        }
+    }
+
+    pub fn window_id(&self) -> WindowId {
+        self.window_id
+    }
+
+    pub fn set_title(&self, title: impl Into<String>) {
+        self.push(WindowCommand::SetTitle(title.into()));
+    }
+
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.push(WindowCommand::SetCursorVisible(visible));
+    }
+
+    /// Locking falls back to confining and vice versa on platforms which support only one of them.
+    pub fn set_cursor_grab(&self, cursor_grab: CursorGrab) {
+        self.push(WindowCommand::SetCursorGrab(cursor_grab));
+    }
+
+    pub fn set_cursor(&self, cursor: Cursor) {
+        self.push(WindowCommand::SetCursor(cursor));
+    }
+
+    /// Input methods compose text only while they are allowed, e.g. while a text field is focused.
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        self.push(WindowCommand::SetImeAllowed(allowed));
+    }
+
+    /// Takes the requested commands of all windows in the order they were requested.
+    pub fn take_commands(&self) -> Vec<(WindowId, WindowCommand)> {
+        self.commands.take()
+    }
+
+    fn push(&self, command: WindowCommand) {
+        self.commands.borrow_mut().push((self.window_id, command));
+    }
+}
@@ -1,139 +1,1000 @@
 use std::cell::RefCell;
-use std::mem::transmute;
-use std::rc::Rc;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use error_stack::{Report, ResultExt};
 pub use error_stack::Result;
 
-use core::{Size, ThrustlerBackend, ThrustlerWindow, WindowEvent};
+use core::{ThrustlerBackend, WindowEvent};
+use core::context::SceneContextParts;
+pub use core::clipboard::{Clipboard, MemoryClipboard};
+pub use core::context::SceneContext;
 pub use core::error::ThrustlerError;
-pub use core::game_objects::{GameObject, Scene, Vertex};
+pub use core::game_objects::{GameObject, Scene, StencilMode, Vertex};
+pub use core::input::{InputEvent, InputState, Viewport};
+pub use core::input::actions::{ActionMap, Actions, Axis2dAction, Axis2dBinding, AxisAction, AxisBinding, ButtonBinding};
+pub use core::input::file_drop::{DroppedFile, FileDropEvent, FileDropState};
+pub use core::input::gamepad::{GamepadAxis, GamepadButton, GamepadEvent, GamepadId, GamepadSource, GamepadState, VirtualGamepad, VirtualGamepadHandle};
+pub use core::input::mouse::{ButtonState, MouseButton, MouseEvent, MouseState, ScrollDelta};
+pub use core::input::text::{TextEvent, TextState};
+pub use core::input::touch::{Gesture, TouchEvent, TouchId, TouchPhase, TouchPoint, TouchState};
+pub use core::lifecycle::LifecycleEvent;
+pub use core::render::{AdapterPreference, PresentMode, RenderSettings};
+pub use core::input::keyboard::{Key, KeyboardEvent, KeyboardState, KeyCode, KeyState, Modifiers, NamedKey};
+pub use core::shader::{Shader, ShaderFiles, ShaderId};
+pub use core::scheduler::{Scheduler, Sequence, TimerEvent, TimerId};
+pub use core::stats::{FrameStats, RenderStats};
+pub use core::time::TimeControl;
+pub use core::{LogicalSize, Size};
+pub use core::window::{Cursor, CursorGrab, CursorImage, Fullscreen, OutputWindow, WindowControl, WindowDescriptor, WindowIcon, WindowId, WindowMetrics, WindowSettings};
 use vulkan::VulkanBackend;
-use vulkan::vulkano_tools::VulkanWindow;
-use wgpu::{WgpuBackend, WgpuWindow};
-use winit_window::{OutputWindow, WinitWindow};
+use wgpu::WgpuBackend;
+use arboard_clipboard::ArboardClipboard;
+use gilrs_gamepad::GilrsGamepad;
+use shader_watcher::ShaderWatcher;
+use stats::StatsCollector;
+pub use replay::{EventPlayer, EventRecorder};
+pub use window::{BackendInitializer, Window, WindowProvider};
 
 mod error;
+pub mod replay;
+mod shader_watcher;
+mod stats;
+mod window;
+
+const DRAW_ATTEMPTS: u32 = 3;
+
+/// Sets up the backend of a single window for its output window.
+type WindowInitializer = Box<dyn Fn(Arc<dyn OutputWindow>) -> Result<(), ThrustlerError>>;
 
 pub struct Engine {
     frames_per_second: u32,
-    window: Box<dyn ThrustlerWindow>,
-    backend: Rc<RefCell<dyn ThrustlerBackend>>,
-    scenes: Vec<Box<dyn Scene>>,
+    stats_log_interval: Option<Duration>,
+    window_provider: Box<dyn WindowProvider>,
+    backend: Backend,
+    render_settings: RenderSettings,
+    windows: Vec<WindowDescriptor>,
+    scenes: Vec<(WindowId, Box<dyn Scene>)>,
+    recorder: Option<EventRecorder>,
+    player: Option<EventPlayer>,
+    action_map: ActionMap,
+    gamepad_sources: Vec<Box<dyn GamepadSource>>,
+    clipboard: Box<dyn Clipboard>,
+    pause_when_unfocused: bool,
+    shader_reload_interval: Option<Duration>,
 }
 
 impl Engine {
     pub fn new_with_settings(engine_settings: EngineSettings) -> Result<Engine, ThrustlerError> {
-        let size = engine_settings.window_size;
-
-        let (backend, initializer) = match engine_settings.backend {
-            Backend::Vulkan => {
-                let backend = Rc::new(RefCell::new(VulkanBackend::new(size)));
-                let rc_backend = backend.clone();
-                let initializer: Box<dyn Fn(Arc<dyn OutputWindow>) -> Result<(), ThrustlerError>> = Box::new(
-                    move |window| {
-                        let vulkan_window = unsafe {
-                            transmute::<Arc<dyn OutputWindow>, Arc<dyn VulkanWindow>>(window)
-                        };
-                        rc_backend.borrow_mut().init(vulkan_window)
-                    });
-                (backend as Rc<RefCell<dyn ThrustlerBackend>>, Some(initializer))
-            }
-            Backend::Wgpu => {
-                let backend = Rc::new(RefCell::new(WgpuBackend::new(size)));
-                let rc_backend = backend.clone();
-                let initializer: Box<dyn Fn(Arc<dyn OutputWindow>) -> Result<(), ThrustlerError>> = Box::new(
-                    move |window| {
-                        let wgpu_window = unsafe {
-                            transmute::<Arc<dyn OutputWindow>, Arc<dyn WgpuWindow>>(window)
-                        };
-                        rc_backend.borrow_mut().init(wgpu_window)
-                    });
-                (backend as Rc<RefCell<dyn ThrustlerBackend>>, Some(initializer))
+        let mut gamepad_sources: Vec<Box<dyn GamepadSource>> = vec![];
+        if engine_settings.gamepads {
+            //the game can still be played without gamepads
+            match GilrsGamepad::new() {
+                Ok(gilrs_gamepad) => gamepad_sources.push(Box::new(gilrs_gamepad)),
+                Err(report) => println!("Gamepads are unavailable: {report:?}"),
             }
-        };
+        }
 
-        let window = match engine_settings.window {
-            Window::Winit => {
-                WinitWindow::new(
-                    size,
-                    initializer
-                        .ok_or(Report::new(ThrustlerError::EngineError))
-                        .attach_printable("Initialization callback is not specified")?,
-                )
+        //scenes still can copy and paste inside the game
+        let clipboard: Box<dyn Clipboard> = match ArboardClipboard::new() {
+            Ok(clipboard) => Box::new(clipboard),
+            Err(report) => {
+                println!("System clipboard is unavailable: {report:?}");
+                Box::new(MemoryClipboard::new())
             }
+        };
+
+        let mut render_settings = engine_settings.render_settings;
+        if let Some(adapter) = AdapterPreference::from_env() {
+            println!("Adapter preference is overridden by {}: {adapter:?}", core::render::ADAPTER_ENV_VAR);
+            render_settings.adapter = adapter;
         }
-            .change_context(ThrustlerError::EngineError)
-            .attach_printable("Window creation error")?;
 
         Ok(Self {
             frames_per_second: engine_settings.frames_per_second,
-            window: Box::new(window),
-            backend,
+            stats_log_interval: engine_settings.stats_log_interval,
+            window_provider: engine_settings.window.into_provider(),
+            backend: engine_settings.backend,
+            render_settings,
+            windows: vec![WindowDescriptor {
+                id: WindowId::PRIMARY,
+                size: engine_settings.window_size,
+                settings: engine_settings.window_settings,
+            }],
             scenes: vec![],
+            recorder: None,
+            player: None,
+            action_map: ActionMap::new(),
+            gamepad_sources,
+            clipboard,
+            pause_when_unfocused: engine_settings.pause_when_unfocused,
+            shader_reload_interval: engine_settings.shader_reload_interval,
         })
     }
 
-    pub fn start(mut self) -> Result<(), ThrustlerError> {
-        let mut previous = Instant::now();
-        let frame_time = 1.0 / (self.frames_per_second as f32);
-        //the time elapsed since last handled frame
-        let mut elapsed_time = 0.0;
-
-        let back_clone = self.backend.clone();
-        self.window.start(Box::new(move |event| {
-            for scene in &mut self.scenes {
-                match event {
-                    WindowEvent::OnStart => scene.on_start(),
-                    WindowEvent::OnDraw => {
-                        elapsed_time += previous.elapsed().as_secs_f32();
-                        previous = Instant::now();
-
-                        while elapsed_time >= frame_time {
-                            scene.on_update();
-                            back_clone.clone().borrow_mut().draw_scene(scene);
-                            //we could still have some time which wasn't taken into account, and we have to use it in future calculations
-                            elapsed_time -= frame_time;
+    pub fn start(self) -> Result<(), ThrustlerError> {
+        for (window_id, _) in &self.scenes {
+            if !self.windows.iter().any(|window| window.id == *window_id) {
+                return Err(Report::new(ThrustlerError::EngineError))
+                    .attach_printable(format!("A scene is added to the unknown window {window_id:?}"));
+            }
+        }
+
+        let window_control = WindowControl::new();
+        let mut windows = vec![];
+        let mut initializers = HashMap::new();
+        for descriptor in &self.windows {
+            //the real size comes from the window when it's opened
+            let size = descriptor.size.to_physical(1.0);
+            let (backend, initializer) = create_backend(&self.backend, &self.render_settings, size);
+            if initializers.insert(descriptor.id, initializer).is_some() {
+                return Err(Report::new(ThrustlerError::EngineError))
+                    .attach_printable(format!("The window {:?} is added twice", descriptor.id));
+            }
+            let viewport = Viewport::new(size, backend.borrow().is_y_axis_up());
+            windows.push(WindowEntry {
+                id: descriptor.id,
+                backend,
+                scenes: vec![],
+                input: InputState::new(viewport),
+                window_control: window_control.for_window(descriptor.id),
+                metrics: WindowMetrics { size, scale_factor: 1.0 },
+                is_focused: true,
+                is_occluded: false,
+                is_hidden: false,
+            });
+        }
+        for (window_id, scene) in self.scenes {
+            if let Some(window) = windows.iter_mut().find(|window| window.id == window_id) {
+                window.scenes.push(SceneEntry::new(scene));
+            }
+        }
+
+        let backend_initializer: BackendInitializer = Box::new(move |window_id, window| {
+            match initializers.get(&window_id) {
+                Some(initializer) => initializer(window),
+                None => Err(Report::new(ThrustlerError::EngineError))
+                    .attach_printable(format!("There is no backend for the window {window_id:?}")),
+            }
+        });
+        let window = self.window_provider
+            .create_windows(self.windows, backend_initializer)
+            .attach_printable("Window creation error")?;
+
+        let game_loop = GameLoop {
+            windows,
+            frame_time: 1.0 / (self.frames_per_second as f32),
+            elapsed_time: 0.0,
+            is_suspended: false,
+            is_surface_released: false,
+            pause_when_unfocused: self.pause_when_unfocused,
+            shader_watcher: self.shader_reload_interval.map(ShaderWatcher::new),
+            state: EngineState {
+                stats: StatsCollector::new(self.stats_log_interval),
+                time_control: TimeControl::new(),
+                action_map: self.action_map,
+                clipboard: self.clipboard,
+            },
+        };
+        let mut dispatcher = EventDispatcher {
+            game_loop,
+            recorder: self.recorder,
+            player: self.player,
+            clock: Box::new(Instant::now),
+            previous: Instant::now(),
+        };
+        let mut gamepad_sources = self.gamepad_sources;
+
+        window.start(Box::new(move |event| {
+            if event == WindowEvent::OnDraw {
+                dispatcher.poll_gamepads(&mut gamepad_sources)?;
+            }
+            dispatcher.dispatch(event)
+        }), window_control)
+    }
+
+    /// Adds a scene to the primary window.
+    pub fn add_scene(self, scene: impl Scene + 'static) -> Engine {
+        self.add_scene_to(WindowId::PRIMARY, scene)
+    }
+
+    pub fn add_scene_to(mut self, window_id: WindowId, scene: impl Scene + 'static) -> Engine {
+        self.scenes.push((window_id, Box::new(scene)));
+        self
+    }
+
+    /// Opens one more window with its own backend, scenes are added to it with [`Engine::add_scene_to`].
+    pub fn add_window(mut self, window: WindowDescriptor) -> Engine {
+        self.windows.push(window);
+        self
+    }
+
+    /// Sets bindings of named actions which scenes query through [`SceneContext::actions`].
+    pub fn with_action_map(mut self, action_map: ActionMap) -> Engine {
+        self.action_map = action_map;
+        self
+    }
+
+    /// Adds a source of gamepad events, e.g. a [`VirtualGamepad`] driven by a test.
+    pub fn with_gamepad_source(mut self, gamepad_source: impl GamepadSource + 'static) -> Engine {
+        self.gamepad_sources.push(Box::new(gamepad_source));
+        self
+    }
+
+    /// Replaces the system clipboard, e.g. with a [`MemoryClipboard`] in tests.
+    pub fn with_clipboard(mut self, clipboard: impl Clipboard + 'static) -> Engine {
+        self.clipboard = Box::new(clipboard);
+        self
+    }
+
+    /// Records every handled event into the recorder, so the session can be replayed later.
+    pub fn with_recorder(mut self, recorder: EventRecorder) -> Engine {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Drives scenes by the recorded events instead of the live ones until the recording is over.
+    pub fn with_player(mut self, player: EventPlayer) -> Engine {
+        self.player = Some(player);
+        self
+    }
+}
+
+/// Tells the current time, frame deltas are measured with it.
+type Clock = Box<dyn FnMut() -> Instant>;
+
+/// Passes live events to the game loop, records them or replaces them with recorded ones.
+struct EventDispatcher {
+    game_loop: GameLoop,
+    recorder: Option<EventRecorder>,
+    player: Option<EventPlayer>,
+    clock: Clock,
+    previous: Instant,
+}
+
+impl EventDispatcher {
+    fn dispatch(&mut self, event: WindowEvent) -> Result<(), ThrustlerError> {
+        let delta = match event {
+            WindowEvent::OnDraw => {
+                let now = (self.clock)();
+                let delta = now.duration_since(self.previous).as_secs_f32();
+                self.previous = now;
+                delta
+            }
+            WindowEvent::Resumed => {
+                //the time spent in background must not be caught up with a burst of updates
+                self.previous = (self.clock)();
+                0.0
+            }
+            _ => 0.0,
+        };
+
+        if let Some(player) = self.player.as_mut().filter(|player| !player.is_finished()) {
+            if replay::is_live_only(&event) {
+                return self.game_loop.handle_event(event, delta, EventScope::All);
+            }
+            if replay::is_window_state(&event) {
+                //the simulation takes the window state from the recording
+                return self.game_loop.handle_event(event, delta, EventScope::Surface);
+            }
+            for recorded_event in player.next_events(&event) {
+                let scope = if replay::is_window_state(&recorded_event.event) { EventScope::Simulation } else { EventScope::All };
+                self.game_loop.handle_event(recorded_event.event, recorded_event.delta, scope)?;
+            }
+            return Ok(());
+        }
+
+        if let Some(Err(report)) = self.recorder.as_mut().map(|recorder| recorder.record(&event, delta)) {
+            println!("Event recording is stopped: {report:?}");
+            self.recorder = None;
+        }
+        let result = self.game_loop.handle_event(event, delta, EventScope::All);
+        if result.is_err() {
+            //the loop is going to be stopped, so the recording has to be complete
+            self.flush_recorder();
+        }
+        result
+    }
+
+    /// Gamepads are polled right before the frame, so their events go the same way as window events.
+    fn poll_gamepads(&mut self, gamepad_sources: &mut [Box<dyn GamepadSource>]) -> Result<(), ThrustlerError> {
+        let gamepad_events = gamepad_sources.iter_mut()
+            .flat_map(|source| source.poll_events())
+            .collect::<Vec<_>>();
+        for gamepad_event in gamepad_events {
+            self.dispatch(WindowEvent::Input(WindowId::PRIMARY, InputEvent::Gamepad(gamepad_event)))?;
+        }
+        Ok(())
+    }
+
+    fn flush_recorder(&mut self) {
+        if let Some(Err(report)) = self.recorder.as_mut().map(|recorder| recorder.flush()) {
+            println!("Event recording can't be flushed: {report:?}");
+        }
+    }
+}
+
+/// Which effects of an event are handled. A replayed session takes the window state which affects the simulation,
+/// e.g. focus or size, from the recording, while the surface still follows the live window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum EventScope {
+    All,
+    Simulation,
+    Surface,
+}
+
+impl EventScope {
+    fn has_simulation(self) -> bool {
+        self != EventScope::Surface
+    }
+
+    fn has_surface(self) -> bool {
+        self != EventScope::Simulation
+    }
+}
+
+struct SceneEntry {
+    scene: Box<dyn Scene>,
+    scheduler: Scheduler,
+}
+
+impl SceneEntry {
+    fn new(scene: Box<dyn Scene>) -> Self {
+        Self {
+            scene,
+            scheduler: Scheduler::new(),
+        }
+    }
+}
+
+struct GameLoop {
+    windows: Vec<WindowEntry>,
+    frame_time: f32,
+    //the time elapsed since last handled frame
+    elapsed_time: f32,
+    //scenes aren't updated while the application is suspended
+    is_suspended: bool,
+    //surfaces are released while the application is suspended, so nothing is drawn
+    is_surface_released: bool,
+    pause_when_unfocused: bool,
+    shader_watcher: Option<ShaderWatcher>,
+    state: EngineState,
+}
+
+/// A window with its own backend, scenes and input.
+struct WindowEntry {
+    id: WindowId,
+    backend: Rc<RefCell<dyn ThrustlerBackend>>,
+    scenes: Vec<SceneEntry>,
+    input: InputState,
+    window_control: WindowControl,
+    metrics: WindowMetrics,
+    is_focused: bool,
+    //occluded windows are updated but don't count as visible for the game
+    is_occluded: bool,
+    //the live window isn't visible, so it isn't drawn, it differs from `is_occluded` only during replay
+    is_hidden: bool,
+}
+
+impl WindowEntry {
+    fn start_scenes(&mut self, state: &mut EngineState) {
+        for entry in &mut self.scenes {
+            entry.scene.on_start(&mut state.context(&self.input, &self.window_control, self.metrics, &mut entry.scheduler));
+        }
+    }
+
+    fn apply_input(&mut self, input_event: &InputEvent, state: &mut EngineState) {
+        self.input.apply(input_event);
+        for entry in &mut self.scenes {
+            entry.scene.on_input(input_event, &mut state.context(&self.input, &self.window_control, self.metrics, &mut entry.scheduler));
+        }
+    }
+
+    fn lifecycle(&mut self, lifecycle_event: LifecycleEvent, state: &mut EngineState) {
+        for entry in &mut self.scenes {
+            entry.scene.on_lifecycle(lifecycle_event, &mut state.context(&self.input, &self.window_control, self.metrics, &mut entry.scheduler));
+        }
+    }
+
+    fn update(&mut self, frame_time: f32, state: &mut EngineState) {
+        self.input.advance(Duration::from_secs_f32(frame_time));
+        for entry in &mut self.scenes {
+            let update_start = Instant::now();
+            let timer_events = entry.scheduler.advance(Duration::from_secs_f32(frame_time));
+            let mut context = state.context(&self.input, &self.window_control, self.metrics, &mut entry.scheduler);
+            for timer_event in timer_events {
+                entry.scene.on_timer(timer_event, &mut context);
+            }
+            entry.scene.on_update(&mut context);
+            state.stats.record_update(update_start.elapsed());
+        }
+        self.input.end_update();
+    }
+
+    /// Draws all scenes of the window into one frame.
+    fn draw(&mut self, state: &mut EngineState) -> Result<(), ThrustlerError> {
+        if self.is_hidden {
+            return Ok(());
+        }
+        let scenes = self.scenes.iter().map(|entry| entry.scene.as_ref()).collect::<Vec<_>>();
+        let render_start = Instant::now();
+        let mut backend = self.backend.borrow_mut();
+        draw_with_retries(&mut *backend, &scenes)?;
+        state.stats.record_render(self.id, render_start.elapsed(), backend.render_stats());
+        Ok(())
+    }
+
+    /// Sets the size which scenes see, e.g. to map the cursor into the scene coordinates.
+    fn set_size(&mut self, size: Size) {
+        self.metrics.size = size;
+        let is_y_axis_up = self.backend.borrow().is_y_axis_up();
+        self.input.set_viewport(Viewport::new(size, is_y_axis_up));
+    }
+
+    fn resize_surface(&mut self, size: Size) -> Result<(), ThrustlerError> {
+        //minimized windows have no size, the surface is kept until they are restored
+        if size.is_empty() {
+            return Ok(());
+        }
+        self.backend.borrow_mut().resize(size)
+    }
+
+    fn destroy_scenes(&mut self, state: &mut EngineState) {
+        for entry in &mut self.scenes {
+            entry.scene.on_destroy(&mut state.context(&self.input, &self.window_control, self.metrics, &mut entry.scheduler));
+            //timers live as long as their scene
+            entry.scheduler.clear();
+        }
+    }
+}
+
+/// Engine state shared by all scenes through [`SceneContext`].
+struct EngineState {
+    stats: StatsCollector,
+    time_control: TimeControl,
+    action_map: ActionMap,
+    clipboard: Box<dyn Clipboard>,
+}
+
+impl EngineState {
+    fn context<'a>(
+        &'a mut self,
+        input: &'a InputState,
+        window_control: &'a WindowControl,
+        window_metrics: WindowMetrics,
+        scheduler: &'a mut Scheduler,
+    ) -> SceneContext<'a> {
+        SceneContext::new(SceneContextParts {
+            frame_stats: self.stats.stats(),
+            input,
+            action_map: &mut self.action_map,
+            time_control: &mut self.time_control,
+            scheduler,
+            window: window_control,
+            window_metrics,
+            clipboard: &mut *self.clipboard,
+        })
+    }
+}
+
+impl GameLoop {
+    fn handle_event(&mut self, event: WindowEvent, delta: f32, scope: EventScope) -> Result<(), ThrustlerError> {
+        match event {
+            WindowEvent::OnStart => {
+                for window in &mut self.windows {
+                    window.start_scenes(&mut self.state);
+                }
+            }
+            WindowEvent::OnDraw => {
+                //the frame ends once the windows drawn after this update have drawn it
+                let drawn_windows = self.windows.iter()
+                    .filter(|window| !window.is_hidden && !self.is_surface_released)
+                    .map(|window| window.id);
+                self.state.stats.begin_frame(drawn_windows);
+                self.reload_shaders();
+                self.advance(delta);
+            }
+            WindowEvent::Redraw(window_id) => {
+                let is_surface_released = self.is_surface_released;
+                let window = self.windows.iter_mut().find(|window| window.id == window_id);
+                if let Some(window) = window.filter(|_| !is_surface_released) {
+                    if let Err(report) = window.draw(&mut self.state) {
+                        //the loop is going to be stopped, so scenes have to be destroyed here
+                        self.destroy_scenes();
+                        return Err(report);
+                    }
+                }
+            }
+            WindowEvent::OnStop => self.destroy_scenes(),
+            WindowEvent::WindowClosed(window_id) => {
+                if let Some(index) = self.windows.iter().position(|window| window.id == window_id) {
+                    //the backend is dropped with the window, so its surface and swapchain don't outlive it
+                    let mut window = self.windows.remove(index);
+                    window.destroy_scenes(&mut self.state);
+                    window.backend.borrow_mut().release_surface();
+                    self.state.stats.remove_window(window_id);
+                }
+            }
+            WindowEvent::Resized(window_id, size) => {
+                if let Some(window) = self.open_window(window_id) {
+                    if scope.has_simulation() {
+                        window.set_size(size);
+                    }
+                    if scope.has_surface() {
+                        if let Err(report) = window.resize_surface(size) {
+                            self.destroy_scenes();
+                            return Err(report);
                         }
                     }
-                    WindowEvent::OnStop => scene.on_destroy(),
                 }
             }
-        }))
+            WindowEvent::ScaleFactorChanged(window_id, scale_factor) => {
+                if let Some(window) = self.open_window(window_id).filter(|_| scope.has_simulation()) {
+                    window.metrics.scale_factor = scale_factor;
+                }
+            }
+            WindowEvent::Suspended => {
+                for window in &mut self.windows {
+                    if scope.has_surface() {
+                        window.backend.borrow_mut().release_surface();
+                    }
+                    if scope.has_simulation() {
+                        window.lifecycle(LifecycleEvent::Suspended, &mut self.state);
+                    }
+                }
+                self.is_surface_released |= scope.has_surface();
+                self.is_suspended |= scope.has_simulation();
+            }
+            WindowEvent::Resumed => {
+                //surfaces are recreated by the window before this event
+                if scope.has_surface() {
+                    self.is_surface_released = false;
+                }
+                if scope.has_simulation() {
+                    self.is_suspended = false;
+                    for window in &mut self.windows {
+                        window.lifecycle(LifecycleEvent::Resumed, &mut self.state);
+                    }
+                }
+            }
+            WindowEvent::Focused(window_id, is_focused) => {
+                let window = self.windows.iter_mut().find(|window| window.id == window_id);
+                if let Some(window) = window.filter(|_| scope.has_simulation()) {
+                    window.is_focused = is_focused;
+                    if !is_focused {
+                        window.input.focus_lost();
+                    }
+                    let lifecycle_event = if is_focused { LifecycleEvent::FocusGained } else { LifecycleEvent::FocusLost };
+                    window.lifecycle(lifecycle_event, &mut self.state);
+                }
+            }
+            WindowEvent::Occluded(window_id, is_occluded) => {
+                let window = self.windows.iter_mut().find(|window| window.id == window_id);
+                if let Some(window) = window {
+                    if scope.has_surface() {
+                        window.is_hidden = is_occluded;
+                    }
+                    if scope.has_simulation() {
+                        window.is_occluded = is_occluded;
+                        let lifecycle_event = if is_occluded { LifecycleEvent::Hidden } else { LifecycleEvent::Shown };
+                        window.lifecycle(lifecycle_event, &mut self.state);
+                    }
+                }
+            }
+            WindowEvent::Input(window_id, input_event) => {
+                //gamepads don't belong to a window, so all windows get their events
+                let is_gamepad_event = matches!(input_event, InputEvent::Gamepad(_));
+                for window in &mut self.windows {
+                    if is_gamepad_event || window.id == window_id {
+                        window.apply_input(&input_event, &mut self.state);
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 
-    pub fn add_scene(mut self, scene: impl Scene + 'static) -> Engine {
-        self.scenes.push(Box::new(scene));
-        self
+    fn open_window(&mut self, window_id: WindowId) -> Option<&mut WindowEntry> {
+        self.windows.iter_mut().find(|window| window.id == window_id)
+    }
+
+    /// Updates scenes by the passed time, windows are drawn separately, so they are drawn while the game is paused as well.
+    fn advance(&mut self, delta: f32) {
+        //the game doesn't go on while nobody sees it
+        if self.is_suspended || self.is_in_background() {
+            return;
+        }
+        if self.state.time_control.is_paused() {
+            //a requested step updates paused scenes exactly once
+            if self.state.time_control.take_step() {
+                self.update();
+            }
+            return;
+        }
+
+        self.elapsed_time += delta * self.state.time_control.time_scale();
+        //a scene could pause the loop during the update, the rest of the time is kept for resuming
+        while !self.state.time_control.is_paused() && self.elapsed_time >= self.frame_time {
+            self.update();
+            //we could still have some time which wasn't taken into account, and we have to use it in future calculations
+            self.elapsed_time -= self.frame_time;
+        }
+    }
+
+    /// Rebuilds pipelines of shaders whose files have changed, backends keep the previous pipelines if the new source fails.
+    fn reload_shaders(&mut self) {
+        let Some(shader_watcher) = &mut self.shader_watcher else {
+            return;
+        };
+        if self.is_surface_released || !shader_watcher.is_check_due() {
+            return;
+        }
+        for window in &self.windows {
+            let objects = window.scenes.iter().flat_map(|entry| entry.scene.get_scene_objects());
+            for shader in objects.filter_map(|object| object.shader.as_ref()) {
+                shader_watcher.watch(shader);
+            }
+        }
+        for shader in shader_watcher.changed_shaders() {
+            for window in &self.windows {
+                match window.backend.borrow_mut().reload_shader(&shader) {
+                    Ok(()) => println!("Shader {:?} is reloaded in the window {:?}", shader.id(), window.id),
+                    Err(report) => println!("Shader {:?} isn't reloaded in the window {:?}, the previous one is kept: {report:?}", shader.id(), window.id),
+                }
+            }
+        }
+    }
+
+    fn is_in_background(&self) -> bool {
+        let is_hidden = self.windows.iter().all(|window| window.is_occluded);
+        let is_unfocused = self.pause_when_unfocused && !self.windows.iter().any(|window| window.is_focused);
+        is_hidden || is_unfocused
+    }
+
+    fn update(&mut self) {
+        for window in &mut self.windows {
+            window.update(self.frame_time, &mut self.state);
+        }
+    }
+
+    fn destroy_scenes(&mut self) {
+        for window in &mut self.windows {
+            window.destroy_scenes(&mut self.state);
+        }
+    }
+}
+
+/// Creates a backend for a single window and the callback which sets it up for the output window.
+fn create_backend(
+    backend: &Backend,
+    render_settings: &RenderSettings,
+    size: Size,
+) -> (Rc<RefCell<dyn ThrustlerBackend>>, WindowInitializer) {
+    match backend {
+        Backend::Vulkan => {
+            let backend = Rc::new(RefCell::new(VulkanBackend::new(size, render_settings.clone())));
+            let weak_backend = Rc::downgrade(&backend);
+            let initializer: WindowInitializer = Box::new(
+                move |window| upgrade_backend(&weak_backend)?.borrow_mut().init(window)
+            );
+            (backend, initializer)
+        }
+        Backend::Wgpu => {
+            let backend = Rc::new(RefCell::new(WgpuBackend::new(size, render_settings.clone())));
+            let weak_backend = Rc::downgrade(&backend);
+            let initializer: WindowInitializer = Box::new(
+                move |window| upgrade_backend(&weak_backend)?.borrow_mut().init(window)
+            );
+            (backend, initializer)
+        }
+    }
+}
+
+/// The backend of an open window, the engine drops backends of closed windows.
+fn upgrade_backend<T>(backend: &Weak<RefCell<T>>) -> Result<Rc<RefCell<T>>, ThrustlerError> {
+    backend.upgrade()
+        .ok_or(Report::new(ThrustlerError::EngineError))
+        .attach_printable("The backend of a closed window can't be initialized")
+}
+
+/// Retries recoverable drawing failures, the frame is skipped if all attempts have failed.
+fn draw_with_retries(backend: &mut dyn ThrustlerBackend, scenes: &[&dyn Scene]) -> Result<(), ThrustlerError> {
+    let mut attempt = 1;
+    loop {
+        match backend.draw_scenes(scenes) {
+            Err(report) if matches!(report.current_context(), ThrustlerError::RecoverableBackendError) => {
+                if attempt >= DRAW_ATTEMPTS {
+                    println!("Frame is skipped after {attempt} draw attempts: {report:?}");
+                    return Ok(());
+                }
+                attempt += 1;
+            }
+            result => return result,
+        }
     }
 }
 
 pub struct EngineSettings {
-    pub window_size: Size,
+    pub window_size: LogicalSize,
     pub frames_per_second: u32,
+    /// Frame stats are printed with this interval, `None` disables logging
+    pub stats_log_interval: Option<Duration>,
+    /// Enables system gamepads, virtual ones can be added with [`Engine::with_gamepad_source`] regardless of it
+    pub gamepads: bool,
+    /// Stops updating scenes while no window of the game is focused
+    pub pause_when_unfocused: bool,
+    /// Shader files are checked for changes with this interval and changed shaders are rebuilt, `None` disables hot reload.
+    /// The built-in shader is reloaded from `assets/shaders/wgsl/simple_shader.wgsl` and others from the files they are loaded from.
+    /// `assets/shaders/wgsl` is listed on every check, so files which are added or renamed there are picked up as well
+    pub shader_reload_interval: Option<Duration>,
+    pub window_settings: WindowSettings,
     pub window: Window,
     pub backend: Backend,
+    /// Settings of the backend, the adapter preference can be overridden by the `THRUSTLER_ADAPTER` environment variable
+    pub render_settings: RenderSettings,
 }
 
 impl Default for EngineSettings {
     fn default() -> Self {
         EngineSettings {
-            window_size: Size::default(),
+            window_size: LogicalSize::default(),
             frames_per_second: 60,
+            stats_log_interval: None,
+            gamepads: true,
+            pause_when_unfocused: false,
+            shader_reload_interval: None,
+            window_settings: WindowSettings::default(),
             window: Window::Winit,
             backend: Backend::Vulkan,
+            render_settings: RenderSettings::default(),
         }
     }
 }
 
-enum Window {
-    Winit,
-}
-
 pub enum Backend {
     Vulkan,
     Wgpu,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use core::game_objects::GameObject;
+    use core::stats::RenderStats;
+
+    use super::*;
+
+    struct NullBackend;
+
+    impl ThrustlerBackend for NullBackend {
+        fn draw_scenes(&mut self, _scenes: &[&dyn Scene]) -> Result<(), ThrustlerError> {
+            Ok(())
+        }
+
+        fn render_stats(&self) -> RenderStats {
+            RenderStats::default()
+        }
+
+        fn resize(&mut self, _size: Size) -> Result<(), ThrustlerError> {
+            Ok(())
+        }
+
+        fn release_surface(&mut self) {}
+
+        fn is_y_axis_up(&self) -> bool {
+            true
+        }
+
+        fn reload_shader(&mut self, _shader: &Shader) -> Result<(), ThrustlerError> {
+            Ok(())
+        }
+    }
+
+    /// Writes down frames it draws and what happens to its surface.
+    struct LogBackend {
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl ThrustlerBackend for LogBackend {
+        fn draw_scenes(&mut self, scenes: &[&dyn Scene]) -> Result<(), ThrustlerError> {
+            self.log.borrow_mut().push(format!("draw {}", scenes.len()));
+            Ok(())
+        }
+
+        fn render_stats(&self) -> RenderStats {
+            RenderStats::default()
+        }
+
+        fn resize(&mut self, _size: Size) -> Result<(), ThrustlerError> {
+            Ok(())
+        }
+
+        fn release_surface(&mut self) {
+            self.log.borrow_mut().push("release".to_string());
+        }
+
+        fn is_y_axis_up(&self) -> bool {
+            true
+        }
+
+        fn reload_shader(&mut self, _shader: &Shader) -> Result<(), ThrustlerError> {
+            Ok(())
+        }
+    }
+
+    impl Drop for LogBackend {
+        fn drop(&mut self) {
+            self.log.borrow_mut().push("drop".to_string());
+        }
+    }
+
+    /// Writes down everything the simulation does, so sessions can be compared.
+    struct LogScene {
+        log: Rc<RefCell<Vec<String>>>,
+        objects: Vec<GameObject>,
+    }
+
+    impl Scene for LogScene {
+        fn on_start(&mut self, _context: &mut SceneContext) {
+            self.log.borrow_mut().push("start".to_string());
+        }
+
+        fn on_update(&mut self, context: &mut SceneContext) {
+            let metrics = context.window_metrics();
+            self.log.borrow_mut().push(format!("update {:?} {}", metrics.size, metrics.scale_factor));
+        }
+
+        fn on_destroy(&mut self, _context: &mut SceneContext) {}
+
+        fn on_input(&mut self, event: &InputEvent, context: &mut SceneContext) {
+            let south = context.input().gamepads().any(|gamepad| gamepad.is_button_down(GamepadButton::South));
+            self.log.borrow_mut().push(format!("{event:?} south down: {south}"));
+        }
+
+        fn on_lifecycle(&mut self, event: LifecycleEvent, _context: &mut SceneContext) {
+            self.log.borrow_mut().push(format!("{event:?}"));
+        }
+
+        fn get_scene_objects(&self) -> &Vec<GameObject> {
+            &self.objects
+        }
+    }
+
+    fn window(id: WindowId, backend: Rc<RefCell<dyn ThrustlerBackend>>, scenes: Vec<LogScene>) -> WindowEntry {
+        let size = Size::new(800, 600);
+        WindowEntry {
+            id,
+            backend,
+            scenes: scenes.into_iter().map(|scene| SceneEntry::new(Box::new(scene))).collect(),
+            input: InputState::new(Viewport::new(size, true)),
+            window_control: WindowControl::new().for_window(id),
+            metrics: WindowMetrics { size, scale_factor: 1.0 },
+            is_focused: true,
+            is_occluded: false,
+            is_hidden: false,
+        }
+    }
+
+    fn dispatcher(log: Rc<RefCell<Vec<String>>>, recorder: Option<EventRecorder>, player: Option<EventPlayer>) -> EventDispatcher {
+        let window = window(WindowId::PRIMARY, Rc::new(RefCell::new(NullBackend)), vec![LogScene { log, objects: vec![] }]);
+        let start = Instant::now();
+        let mut now = start;
+        EventDispatcher {
+            game_loop: GameLoop {
+                windows: vec![window],
+                frame_time: 0.01,
+                elapsed_time: 0.0,
+                is_suspended: false,
+                is_surface_released: false,
+                pause_when_unfocused: true,
+                shader_watcher: None,
+                state: EngineState {
+                    stats: StatsCollector::new(None),
+                    time_control: TimeControl::new(),
+                    action_map: ActionMap::new(),
+                    clipboard: Box::new(MemoryClipboard::new()),
+                },
+            },
+            recorder,
+            player,
+            clock: Box::new(move || {
+                //every reading is a frame later, so updates don't depend on the speed of the machine
+                now += Duration::from_millis(25);
+                now
+            }),
+            previous: start,
+        }
+    }
+
+    fn draw(dispatcher: &mut EventDispatcher) {
+        dispatcher.dispatch(WindowEvent::OnDraw).unwrap();
+    }
+
+    #[test]
+    fn replayed_session_reproduces_window_state() {
+        let path = env::temp_dir().join(format!("thrustler-replay-{}.jsonl", std::process::id()));
+
+        let recorded_log = Rc::new(RefCell::new(vec![]));
+        let mut recording = dispatcher(recorded_log.clone(), Some(EventRecorder::create(&path).unwrap()), None);
+        recording.dispatch(WindowEvent::OnStart).unwrap();
+        draw(&mut recording);
+        recording.dispatch(WindowEvent::Focused(WindowId::PRIMARY, false)).unwrap();
+        draw(&mut recording);
+        recording.dispatch(WindowEvent::Focused(WindowId::PRIMARY, true)).unwrap();
+        recording.dispatch(WindowEvent::Resized(WindowId::PRIMARY, Size::new(400, 300))).unwrap();
+        recording.dispatch(WindowEvent::ScaleFactorChanged(WindowId::PRIMARY, 2.0)).unwrap();
+        draw(&mut recording);
+        //the engine could stop without `OnStop`, the recording is flushed anyway
+        drop(recording);
+
+        let replayed_log = Rc::new(RefCell::new(vec![]));
+        let mut replaying = dispatcher(replayed_log.clone(), None, Some(EventPlayer::open(&path).unwrap()));
+        replaying.dispatch(WindowEvent::OnStart).unwrap();
+        while !replaying.player.as_ref().unwrap().is_finished() {
+            replaying.dispatch(WindowEvent::OnDraw).unwrap();
+        }
+        let _ = std::fs::remove_file(&path);
+
+        let recorded_log = recorded_log.borrow();
+        assert!(recorded_log.contains(&"FocusLost".to_string()));
+        //25 ms frames take two updates of 10 ms and keep the rest, no update happens while the window is unfocused
+        assert_eq!(recorded_log.iter().filter(|entry| entry.starts_with("update")).count(), 5);
+        assert!(recorded_log.iter().any(|entry| entry.starts_with("update Size { width: 400, height: 300 } 2")));
+        assert_eq!(*replayed_log.borrow(), *recorded_log);
+    }
+
+    #[test]
+    fn virtual_gamepad_events_reach_scenes() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut dispatcher = dispatcher(log.clone(), None, None);
+        let (gamepad, handle) = VirtualGamepad::new(GamepadId(7));
+        let mut gamepad_sources: Vec<Box<dyn GamepadSource>> = vec![Box::new(gamepad)];
+        dispatcher.dispatch(WindowEvent::OnStart).unwrap();
+
+        handle.connect("Virtual pad");
+        handle.press(GamepadButton::South);
+        handle.set_axis(GamepadAxis::LeftStickX, 2.0);
+        dispatcher.poll_gamepads(&mut gamepad_sources).unwrap();
+
+        let expected = [
+            (GamepadEvent::Connected { id: GamepadId(7), name: "Virtual pad".to_string() }, false),
+            (GamepadEvent::Button { id: GamepadId(7), button: GamepadButton::South, state: ButtonState::Pressed }, true),
+            (GamepadEvent::Axis { id: GamepadId(7), axis: GamepadAxis::LeftStickX, value: 1.0 }, true),
+        ];
+        let expected_log = std::iter::once("start".to_string())
+            .chain(expected.into_iter().map(|(event, south)| format!("{:?} south down: {south}", InputEvent::Gamepad(event))))
+            .collect::<Vec<_>>();
+        assert_eq!(*log.borrow(), expected_log);
+
+        //events are delivered once
+        dispatcher.poll_gamepads(&mut gamepad_sources).unwrap();
+        assert_eq!(log.borrow().len(), expected_log.len());
+    }
+
+    #[test]
+    fn windows_draw_their_scenes_in_one_frame_and_release_backends_when_closed() {
+        let scene_log = Rc::new(RefCell::new(vec![]));
+        let backend_log = Rc::new(RefCell::new(vec![]));
+        let secondary = WindowId(1);
+        let scenes = vec![
+            LogScene { log: scene_log.clone(), objects: vec![] },
+            LogScene { log: scene_log.clone(), objects: vec![] },
+        ];
+        let mut dispatcher = dispatcher(scene_log.clone(), None, None);
+        dispatcher.game_loop.windows.push(window(secondary, Rc::new(RefCell::new(LogBackend { log: backend_log.clone() })), scenes));
+
+        dispatcher.dispatch(WindowEvent::OnStart).unwrap();
+        dispatcher.dispatch(WindowEvent::Redraw(secondary)).unwrap();
+        assert_eq!(*backend_log.borrow(), vec!["draw 2"]);
+
+        dispatcher.dispatch(WindowEvent::WindowClosed(secondary)).unwrap();
+        assert_eq!(*backend_log.borrow(), vec!["draw 2", "release", "drop"]);
+        //events of the closed window are dropped
+        dispatcher.dispatch(WindowEvent::Redraw(secondary)).unwrap();
+        assert_eq!(backend_log.borrow().len(), 3);
+    }
+}
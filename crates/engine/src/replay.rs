@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use error_stack::{Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use core::error::ThrustlerError;
+use core::WindowEvent;
+
+/// A single entry of a recorded session.
+/// `delta` is the time in seconds passed since the previous `OnDraw`, it is zero for other events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RecordedEvent {
+    pub event: WindowEvent,
+    pub delta: f32,
+}
+
+/// Writes every event handled by the engine into a file, one JSON object per line.
+pub struct EventRecorder {
+    writer: BufWriter<File>,
+}
+
+impl EventRecorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<EventRecorder, ThrustlerError> {
+        let file = File::create(path.as_ref())
+            .attach_printable_lazy(|| format!("Can't create recording file {:?}", path.as_ref()))
+            .change_context(ThrustlerError::EngineError)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: &WindowEvent, delta: f32) -> Result<(), ThrustlerError> {
+        //redraws don't change the simulation
+        if matches!(event, WindowEvent::Redraw(_)) {
+            return Ok(());
+        }
+        let recorded_event = RecordedEvent {
+            event: event.clone(),
+            delta,
+        };
+
+        serde_json::to_writer(&mut self.writer, &recorded_event)
+            .attach_printable("Can't serialize recorded event")
+            .change_context(ThrustlerError::EngineError)?;
+        writeln!(self.writer)
+            .attach_printable("Can't write recorded event")
+            .change_context(ThrustlerError::EngineError)?;
+
+        if *event == WindowEvent::OnStop {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn flush(&mut self) -> Result<(), ThrustlerError> {
+        self.writer.flush()
+            .attach_printable("Can't flush recording file")
+            .change_context(ThrustlerError::EngineError)
+    }
+}
+
+impl Drop for EventRecorder {
+    fn drop(&mut self) {
+        //the engine could be stopped without `OnStop`, e.g. by an error
+        if let Err(report) = self.flush() {
+            println!("Event recording can't be flushed: {report:?}");
+        }
+    }
+}
+
+/// Feeds a session written by [`EventRecorder`] back into the engine.
+/// While the player has events, live events are only used as a clock: every live `OnDraw`
+/// replays recorded events up to the next recorded `OnDraw` with their recorded time deltas.
+pub struct EventPlayer {
+    events: VecDeque<RecordedEvent>,
+}
+
+impl EventPlayer {
+    pub fn open(path: impl AsRef<Path>) -> Result<EventPlayer, ThrustlerError> {
+        let file = File::open(path.as_ref())
+            .attach_printable_lazy(|| format!("Can't open recording file {:?}", path.as_ref()))
+            .change_context(ThrustlerError::EngineError)?;
+
+        let events = BufReader::new(file)
+            .lines()
+            .filter(|line| line.as_ref().map(|line| !line.trim().is_empty()).unwrap_or(true))
+            .map(|line| {
+                let line = line
+                    .attach_printable("Can't read recording file")
+                    .change_context(ThrustlerError::EngineError)?;
+                serde_json::from_str::<RecordedEvent>(&line)
+                    .attach_printable_lazy(|| format!("Malformed recorded event {line}"))
+                    .change_context(ThrustlerError::EngineError)
+            })
+            .collect::<Result<VecDeque<_>, ThrustlerError>>()?;
+
+        Ok(Self { events })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns recorded events which have to be handled instead of the live one.
+    pub(crate) fn next_events(&mut self, live_event: &WindowEvent) -> Vec<RecordedEvent> {
+        match live_event {
+            WindowEvent::OnStart | WindowEvent::OnDraw => self.take_until(live_event),
+            _ => vec![],
+        }
+    }
+
+    fn take_until(&mut self, last_event: &WindowEvent) -> Vec<RecordedEvent> {
+        let mut events = vec![];
+        while let Some(recorded_event) = self.events.pop_front() {
+            if is_live_only(&recorded_event.event) {
+                continue;
+            }
+            let is_last = recorded_event.event == *last_event;
+            events.push(recorded_event);
+            if is_last {
+                break;
+            }
+        }
+        events
+    }
+}
+
+/// Events which end the session or a window or draw it, they are always handled live and never replayed,
+/// the recording can't reopen a window.
+pub(crate) fn is_live_only(event: &WindowEvent) -> bool {
+    matches!(event, WindowEvent::OnStop | WindowEvent::WindowClosed(_) | WindowEvent::Redraw(_))
+}
+
+/// Events which change the window state, it affects both the simulation, e.g. whether scenes are updated,
+/// and the surface. During replay the simulation takes them from the recording and the surface from the live window.
+pub(crate) fn is_window_state(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::Resized(..)
+            | WindowEvent::ScaleFactorChanged(..)
+            | WindowEvent::Suspended
+            | WindowEvent::Resumed
+            | WindowEvent::Focused(..)
+            | WindowEvent::Occluded(..)
+    )
+}
@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use core::shader::{Shader, ShaderFiles, ShaderId};
+
+/// Polls modification times of shader files and loads the shaders again when their files change.
+/// The shaders directory is listed on every check, so files which are added or renamed there are picked up as well,
+/// e.g. when an editor saves a file by renaming a temporary one.
+pub(crate) struct ShaderWatcher {
+    shaders_dir: PathBuf,
+    //shaders loaded from files, they are loaded again when their files change
+    shaders: HashMap<ShaderId, Shader>,
+    //modification times of files in the shaders directory and of watched shaders, `None` if a file is missing
+    files: HashMap<PathBuf, Option<SystemTime>>,
+    check_interval: Duration,
+    last_check: Instant,
+}
+
+impl ShaderWatcher {
+    /// Starts watching [`ShaderFiles::wgsl_dir`] and the built-in shader, shaders of game objects are added with [`ShaderWatcher::watch`].
+    pub fn new(check_interval: Duration) -> Self {
+        let mut watcher = Self::with_dir(ShaderFiles::wgsl_dir(), check_interval);
+        match Shader::built_in() {
+            Ok(shader) => watcher.watch(&shader),
+            Err(report) => println!("The built-in shader isn't watched: {report:?}"),
+        }
+        watcher
+    }
+
+    fn with_dir(shaders_dir: PathBuf, check_interval: Duration) -> Self {
+        let mut watcher = Self {
+            shaders_dir,
+            shaders: HashMap::new(),
+            files: HashMap::new(),
+            check_interval,
+            last_check: Instant::now(),
+        };
+        //files which are already there aren't reported as added
+        for path in watcher.shader_files() {
+            let modified = modification_time(&path);
+            watcher.files.insert(path, modified);
+        }
+        watcher
+    }
+
+    pub fn is_check_due(&self) -> bool {
+        self.last_check.elapsed() >= self.check_interval
+    }
+
+    /// Remembers the shader if it's loaded from a file, shaders made of strings can't be reloaded.
+    pub fn watch(&mut self, shader: &Shader) {
+        let Some(path) = shader.files().map(|files| &files.wgsl) else {
+            return;
+        };
+        if self.shaders.contains_key(&shader.id()) {
+            return;
+        }
+        self.files.entry(path.to_path_buf()).or_insert_with(|| modification_time(path));
+        self.shaders.insert(shader.id(), shader.clone());
+    }
+
+    /// Shaders whose files have changed, appeared or disappeared since the previous check, loaded from the new files.
+    /// A shader which can't be read is reported and checked again when its file changes once more.
+    /// A new file in the shaders directory is only reported, it's applied once a game object loads it.
+    pub fn changed_shaders(&mut self) -> Vec<Shader> {
+        self.last_check = Instant::now();
+        let mut changed_files = HashSet::new();
+        for path in self.shader_files() {
+            let modified = modification_time(&path);
+            if self.files.insert(path.clone(), modified) != Some(modified) {
+                changed_files.insert(path);
+            }
+        }
+
+        let mut changed = vec![];
+        for shader in self.shaders.values_mut() {
+            if !shader.files().is_some_and(|files| changed_files.remove(&files.wgsl)) {
+                continue;
+            }
+            match shader.reload() {
+                Ok(reloaded) => {
+                    *shader = reloaded.clone();
+                    changed.push(reloaded);
+                }
+                Err(report) => println!("Shader {:?} can't be reloaded: {report:?}", shader.id()),
+            }
+        }
+        for path in changed_files.iter().filter(|path| path.exists()) {
+            println!("Shader file {} isn't used by game objects, it's applied once one loads it", path.display());
+        }
+        changed
+    }
+
+    /// WGSL files of the shaders directory and files of watched shaders, which could be elsewhere or missing.
+    fn shader_files(&self) -> HashSet<PathBuf> {
+        let mut paths = self.files.keys().cloned().collect::<HashSet<_>>();
+        //the directory could be missing, e.g. in a shipped game without shader sources
+        if let Ok(entries) = fs::read_dir(&self.shaders_dir) {
+            let wgsl_files = entries.flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && path.extension() == Some(OsStr::new("wgsl")));
+            paths.extend(wgsl_files);
+        }
+        paths
+    }
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::File;
+
+    use super::*;
+
+    fn write_shader(path: &Path, source: &str, modified: u64) {
+        fs::write(path, source).unwrap();
+        //file systems could have a coarse time resolution, so the time is set explicitly
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(modified);
+        File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn renamed_and_added_files_are_picked_up() {
+        let dir = env::temp_dir().join(format!("thrustler-shaders-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("glow.wgsl");
+        write_shader(&path, "first", 1);
+
+        let mut watcher = ShaderWatcher::with_dir(dir.clone(), Duration::ZERO);
+        let shader = Shader::load(ShaderFiles { wgsl: path.clone() }).unwrap();
+        watcher.watch(&shader);
+        assert!(watcher.changed_shaders().is_empty());
+
+        //an editor saves the file by renaming a temporary one over it
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.changed_shaders().is_empty());
+        let temporary = dir.join("glow.wgsl.tmp");
+        write_shader(&temporary, "second", 2);
+        fs::rename(&temporary, &path).unwrap();
+        let changed = watcher.changed_shaders();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].id(), shader.id());
+        assert_eq!(changed[0].wgsl(), "second");
+        assert!(watcher.changed_shaders().is_empty());
+
+        //a new file is tracked from the check which finds it, so its shader is reloaded once it changes
+        let added = dir.join("added.wgsl");
+        write_shader(&added, "added", 3);
+        assert!(watcher.changed_shaders().is_empty());
+        let added_shader = Shader::load(ShaderFiles { wgsl: added.clone() }).unwrap();
+        watcher.watch(&added_shader);
+        write_shader(&added, "changed", 4);
+        let changed = watcher.changed_shaders();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].wgsl(), "changed");
+    }
+}
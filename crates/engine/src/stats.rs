@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use core::stats::{FrameStats, RenderStats};
+use core::window::WindowId;
+
+/// Collects [`FrameStats`] while the game loop runs and logs them periodically if requested.
+/// A frame begins before scenes are updated and ends once its windows have drawn it, so update and render times belong to the same frame.
+pub(crate) struct StatsCollector {
+    //stats of the last complete frame, scenes see them
+    stats: FrameStats,
+    //stats of the frame in progress
+    frame: FrameStats,
+    //windows which haven't drawn the frame in progress yet, `None` if no frame is in progress
+    undrawn_windows: Option<HashSet<WindowId>>,
+    log_interval: Option<Duration>,
+    last_log: Instant,
+    last_frame: Option<Instant>,
+    fps_window_start: Instant,
+    frames_in_fps_window: u32,
+    //every window has its own backend, so their caches are summed up
+    cached_buffers: HashMap<WindowId, usize>,
+}
+
+impl StatsCollector {
+    pub fn new(log_interval: Option<Duration>) -> Self {
+        let now = Instant::now();
+        Self {
+            stats: FrameStats::default(),
+            frame: FrameStats::default(),
+            undrawn_windows: None,
+            log_interval,
+            last_log: now,
+            last_frame: None,
+            fps_window_start: now,
+            frames_in_fps_window: 0,
+            cached_buffers: HashMap::new(),
+        }
+    }
+
+    /// Stats of the last complete frame.
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// Starts a new frame before scenes are updated, it ends once all `windows` have drawn it.
+    /// A frame which isn't complete yet, e.g. because one of its windows isn't redrawn, ends here.
+    pub fn begin_frame(&mut self, windows: impl IntoIterator<Item = WindowId>) {
+        if self.undrawn_windows.is_some() {
+            self.end_frame();
+        }
+        self.frame.update_time = Duration::ZERO;
+        self.frame.render_time = Duration::ZERO;
+        //caches outlive frames, so they are kept till windows are drawn again
+        self.frame.render = RenderStats {
+            cached_buffers: self.frame.render.cached_buffers,
+            ..RenderStats::default()
+        };
+        self.undrawn_windows = Some(windows.into_iter().collect());
+    }
+
+    pub fn record_update(&mut self, update_time: Duration) {
+        self.frame.update_time += update_time;
+    }
+
+    pub fn record_render(&mut self, window_id: WindowId, render_time: Duration, render_stats: RenderStats) {
+        self.frame.render_time += render_time;
+        self.frame.render.draw_calls += render_stats.draw_calls;
+        self.frame.render.uploaded_bytes += render_stats.uploaded_bytes;
+        self.frame.total_uploaded_bytes += render_stats.uploaded_bytes;
+        self.cached_buffers.insert(window_id, render_stats.cached_buffers);
+        self.frame.render.cached_buffers = self.cached_buffers.values().sum();
+        self.mark_drawn(window_id);
+    }
+
+    /// Drops the buffers of a closed window from the stats, the frame in progress doesn't wait for it anymore.
+    pub fn remove_window(&mut self, window_id: WindowId) {
+        self.cached_buffers.remove(&window_id);
+        self.frame.render.cached_buffers = self.cached_buffers.values().sum();
+        self.stats.render.cached_buffers = self.frame.render.cached_buffers;
+        self.mark_drawn(window_id);
+    }
+
+    fn mark_drawn(&mut self, window_id: WindowId) {
+        let Some(undrawn_windows) = &mut self.undrawn_windows else {
+            return;
+        };
+        undrawn_windows.remove(&window_id);
+        if undrawn_windows.is_empty() {
+            self.end_frame();
+        }
+    }
+
+    fn end_frame(&mut self) {
+        self.undrawn_windows = None;
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            self.frame.frame_time = now - last_frame;
+            self.frame.frame_time_histogram.add(self.frame.frame_time);
+        }
+        self.last_frame = Some(now);
+        self.frame.frame_count += 1;
+
+        self.frames_in_fps_window += 1;
+        let fps_window = now - self.fps_window_start;
+        if fps_window >= Duration::from_secs(1) {
+            self.frame.frames_per_second = self.frames_in_fps_window as f32 / fps_window.as_secs_f32();
+            self.frames_in_fps_window = 0;
+            self.fps_window_start = now;
+        }
+        self.stats = self.frame.clone();
+
+        if let Some(log_interval) = self.log_interval {
+            if now - self.last_log >= log_interval {
+                println!("Frame stats: {}", self.stats);
+                self.last_log = now;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_stats_are_summed_across_windows() {
+        let mut collector = StatsCollector::new(None);
+        let secondary = WindowId(1);
+        let render_stats = |draw_calls, cached_buffers| RenderStats { draw_calls, cached_buffers, uploaded_bytes: 16 };
+
+        collector.begin_frame([WindowId::PRIMARY, secondary]);
+        collector.record_update(Duration::from_millis(4));
+        collector.record_render(WindowId::PRIMARY, Duration::from_millis(1), render_stats(2, 5));
+        //the frame isn't complete till all its windows have drawn it
+        assert_eq!(collector.stats().frame_count, 0);
+        collector.record_render(secondary, Duration::from_millis(2), render_stats(3, 7));
+        assert_eq!(collector.stats().frame_count, 1);
+        assert_eq!(collector.stats().update_time, Duration::from_millis(4));
+        assert_eq!(collector.stats().render.draw_calls, 5);
+        assert_eq!(collector.stats().render.cached_buffers, 12);
+        assert_eq!(collector.stats().render.uploaded_bytes, 32);
+        assert_eq!(collector.stats().render_time, Duration::from_millis(3));
+
+        //a window which isn't drawn in the frame keeps its buffers, the frame ends when the next one begins
+        collector.begin_frame([WindowId::PRIMARY, secondary]);
+        collector.record_render(WindowId::PRIMARY, Duration::from_millis(1), render_stats(1, 4));
+        collector.begin_frame([WindowId::PRIMARY]);
+        assert_eq!(collector.stats().frame_count, 2);
+        assert_eq!(collector.stats().update_time, Duration::ZERO);
+        assert_eq!(collector.stats().render.draw_calls, 1);
+        assert_eq!(collector.stats().render.cached_buffers, 11);
+
+        collector.remove_window(secondary);
+        assert_eq!(collector.stats().render.cached_buffers, 4);
+    }
+}
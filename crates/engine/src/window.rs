@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use error_stack::{Result, ResultExt};
+
+use core::ThrustlerWindow;
+use core::error::ThrustlerError;
+use core::window::{OutputWindow, WindowDescriptor, WindowId};
+use winit_window::WinitWindow;
+
+/// Sets up the graphical backend of a window for its output window.
+/// A window has to call it for every opened window before it dispatches `OnStart`.
+pub type BackendInitializer = Box<dyn Fn(WindowId, Arc<dyn OutputWindow>) -> Result<(), ThrustlerError>>;
+
+/// Creates the windows the engine runs in, e.g. SDL windows, a test window or a window of an embedding host.
+/// A provider which can't open several windows should return an error for more than one descriptor.
+pub trait WindowProvider {
+    fn create_windows(
+        self: Box<Self>,
+        windows: Vec<WindowDescriptor>,
+        backend_initializer: BackendInitializer,
+    ) -> Result<Box<dyn ThrustlerWindow>, ThrustlerError>;
+}
+
+pub enum Window {
+    Winit,
+    Custom(Box<dyn WindowProvider>),
+}
+
+impl Window {
+    pub(crate) fn into_provider(self) -> Box<dyn WindowProvider> {
+        match self {
+            Window::Winit => Box::new(WinitWindowProvider),
+            Window::Custom(provider) => provider,
+        }
+    }
+}
+
+struct WinitWindowProvider;
+
+impl WindowProvider for WinitWindowProvider {
+    fn create_windows(
+        self: Box<Self>,
+        windows: Vec<WindowDescriptor>,
+        backend_initializer: BackendInitializer,
+    ) -> Result<Box<dyn ThrustlerWindow>, ThrustlerError> {
+        let window = WinitWindow::new(windows, backend_initializer)
+            .change_context(ThrustlerError::WindowError)?;
+        Ok(Box::new(window))
+    }
+}
@@ -0,0 +1,113 @@
+use error_stack::{Report, Result};
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use core::error::ThrustlerError;
+use core::input::gamepad::{GamepadAxis, GamepadButton, GamepadEvent, GamepadId, GamepadSource};
+use core::input::mouse::ButtonState;
+
+/// Gamepads available to the system, backed by gilrs.
+pub struct GilrsGamepad {
+    gilrs: Gilrs,
+    is_started: bool,
+}
+
+impl GilrsGamepad {
+    pub fn new() -> Result<GilrsGamepad, ThrustlerError> {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            //the platform isn't supported, the dummy context reports no gamepads
+            Err(gilrs::Error::NotImplemented(gilrs)) => gilrs,
+            Err(err) => {
+                return Err(Report::new(ThrustlerError::GamepadError)
+                    .attach_printable(format!("Can't initialize gilrs: {err}")));
+            }
+        };
+
+        Ok(Self {
+            gilrs,
+            is_started: false,
+        })
+    }
+
+    /// Gamepads connected before the start don't send connection events, so they are reported here.
+    fn connected_gamepads(&self) -> Vec<GamepadEvent> {
+        self.gilrs.gamepads()
+            .map(|(id, gamepad)| GamepadEvent::Connected {
+                id: to_gamepad_id(id),
+                name: gamepad.name().to_string(),
+            })
+            .collect()
+    }
+}
+
+impl GamepadSource for GilrsGamepad {
+    fn poll_events(&mut self) -> Vec<GamepadEvent> {
+        let mut events = if self.is_started {
+            vec![]
+        } else {
+            self.is_started = true;
+            self.connected_gamepads()
+        };
+
+        while let Some(event) = self.gilrs.next_event() {
+            let id = to_gamepad_id(event.id);
+            let gamepad_event = match event.event {
+                EventType::Connected => Some(GamepadEvent::Connected {
+                    id,
+                    name: self.gilrs.gamepad(event.id).name().to_string(),
+                }),
+                EventType::Disconnected => Some(GamepadEvent::Disconnected { id }),
+                EventType::ButtonPressed(button, _) => to_gamepad_button(button)
+                    .map(|button| GamepadEvent::Button { id, button, state: ButtonState::Pressed }),
+                EventType::ButtonReleased(button, _) => to_gamepad_button(button)
+                    .map(|button| GamepadEvent::Button { id, button, state: ButtonState::Released }),
+                EventType::AxisChanged(axis, value, _) => to_gamepad_axis(axis)
+                    .map(|axis| GamepadEvent::Axis { id, axis, value }),
+                _ => None,
+            };
+            events.extend(gamepad_event);
+        }
+        events
+    }
+}
+
+fn to_gamepad_id(id: gilrs::GamepadId) -> GamepadId {
+    GamepadId(usize::from(id) as u32)
+}
+
+fn to_gamepad_button(button: Button) -> Option<GamepadButton> {
+    let button = match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftTrigger,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger2,
+        Button::RightTrigger => GamepadButton::RightTrigger,
+        Button::RightTrigger2 => GamepadButton::RightTrigger2,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::LeftThumb => GamepadButton::LeftThumb,
+        Button::RightThumb => GamepadButton::RightThumb,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    };
+    Some(button)
+}
+
+fn to_gamepad_axis(axis: Axis) -> Option<GamepadAxis> {
+    let axis = match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        Axis::LeftZ => GamepadAxis::LeftZ,
+        Axis::RightZ => GamepadAxis::RightZ,
+        _ => return None,
+    };
+    Some(axis)
+}
@@ -1,6 +1,7 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use error_stack::{Result, ResultExt};
+use error_stack::{Report, Result, ResultExt};
 use vulkano::command_buffer::allocator::{StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo};
 use vulkano::instance::debug::DebugUtilsMessenger;
 use vulkano::memory::allocator::StandardMemoryAllocator;
@@ -8,17 +9,21 @@ use vulkano::memory::allocator::StandardMemoryAllocator;
 use core::{Size, ThrustlerBackend};
 use core::error::ThrustlerError;
 use core::game_objects::Scene;
-use vulkano_tools::VulkanWindow;
+use core::render::RenderSettings;
+use core::shader::{Shader, ShaderId};
+use core::stats::RenderStats;
+use core::window::OutputWindow;
 
-use crate::shaders::{simple_fragment_shader, simple_vertex_shader};
 use crate::vulkano_tools::*;
 
 pub mod vulkano_tools;
-mod shaders;
 
 pub struct VulkanBackend {
     screen_size: Size,
+    render_settings: RenderSettings,
     vulkano_toolkit: Option<VulkanoToolkit>,
+    //the toolkit is dropped with the surface, so reloaded shaders are applied again when it's created
+    reloaded_shaders: HashMap<ShaderId, Shader>,
 }
 
 struct VulkanoToolkit {
@@ -31,38 +36,96 @@ struct VulkanoToolkit {
 impl VulkanBackend {
     pub fn new(
         size: Size,
+        render_settings: RenderSettings,
     ) -> VulkanBackend {
         Self {
             screen_size: size,
+            render_settings,
             vulkano_toolkit: None,
+            reloaded_shaders: HashMap::new(),
         }
     }
 
-    fn get_toolkit(&mut self) -> &mut VulkanoToolkit {
-        self.vulkano_toolkit.as_mut().unwrap()
+    fn get_toolkit(&mut self) -> Result<&mut VulkanoToolkit, ThrustlerError> {
+        self.vulkano_toolkit.as_mut()
+            .ok_or(Report::new(ThrustlerError::GraphicalBackendError))
+            .attach_printable("Vulkan backend isn't initialized")
     }
 
-    pub fn init(&mut self, window: Arc<dyn VulkanWindow>) -> Result<(), ThrustlerError> {
-        let toolkit = create_vulkano_toolkit(self.screen_size, window)
+    pub fn init(&mut self, window: Arc<dyn OutputWindow>) -> Result<(), ThrustlerError> {
+        let mut toolkit = create_vulkano_toolkit(self.screen_size, &self.render_settings, window)
             .change_context(ThrustlerError::GraphicalBackendError)
             .attach_printable("Vulkan toolkit initialization error")?;
+        for shader in self.reloaded_shaders.values() {
+            //the surface is still usable with the shaders compiled in
+            if let Err(report) = toolkit.command_buffer_executor.reload_shader(shader) {
+                println!("Reloaded shader {:?} can't be applied again: {report:?}", shader.id());
+            }
+        }
         self.vulkano_toolkit = Some(toolkit);
         Ok(())
     }
 }
 
 impl ThrustlerBackend for VulkanBackend {
-    fn draw_scene(&mut self, scene: &Box<dyn Scene>) {
-        let toolkit = self.get_toolkit();
-        let game_objects = scene.get_scene_objects();
+    fn draw_scenes(&mut self, scenes: &[&dyn Scene]) -> Result<(), ThrustlerError> {
+        let toolkit = self.get_toolkit()?;
+        let scene_objects = scenes.iter().map(|scene| scene.get_scene_objects()).collect::<Vec<_>>();
+        toolkit.command_buffer_executor.prepare_pipelines(&scene_objects);
+
+        match toolkit.command_buffer_executor.execute_buffer(&scene_objects) {
+            BufferExecutorResult::Done => Ok(()),
+            BufferExecutorResult::Recreate => {
+                toolkit.command_buffer_executor.recreate_swapchain()
+                    .change_context(ThrustlerError::GraphicalBackendError)?;
+                Err(Report::new(ThrustlerError::RecoverableBackendError))
+                    .attach_printable("Swapchain was out of date and has been recreated")
+            }
+            BufferExecutorResult::Fail => Err(Report::new(ThrustlerError::GraphicalBackendError))
+                .attach_printable("Command buffer execution failed"),
+        }
+    }
+
+    fn render_stats(&self) -> RenderStats {
+        self.vulkano_toolkit.as_ref()
+            .map(|toolkit| toolkit.command_buffer_executor.render_stats())
+            .unwrap_or_default()
+    }
+
+    fn resize(&mut self, size: Size) -> Result<(), ThrustlerError> {
+        self.screen_size = size;
+        //the toolkit takes the size when it's initialized
+        let Some(toolkit) = self.vulkano_toolkit.as_mut() else {
+            return Ok(());
+        };
+        toolkit.command_buffer_executor.resize(size)
+            .change_context(ThrustlerError::GraphicalBackendError)
+            .attach_printable("Swapchain resizing error")
+    }
+
+    fn release_surface(&mut self) {
+        //the whole toolkit is built around the surface, so it's created again by `init`
+        self.vulkano_toolkit = None;
+    }
 
-        toolkit.command_buffer_executor.execute_buffer(game_objects);
+    fn is_y_axis_up(&self) -> bool {
+        false
+    }
+
+    fn reload_shader(&mut self, shader: &Shader) -> Result<(), ThrustlerError> {
+        if let Some(toolkit) = self.vulkano_toolkit.as_mut() {
+            toolkit.command_buffer_executor.reload_shader(shader)
+                .change_context(ThrustlerError::ShaderError)?;
+        }
+        self.reloaded_shaders.insert(shader.id(), shader.clone());
+        Ok(())
     }
 }
 
 fn create_vulkano_toolkit(
     size: Size,
-    window: Arc<dyn VulkanWindow>,
+    render_settings: &RenderSettings,
+    window: Arc<dyn OutputWindow>,
 ) -> Result<VulkanoToolkit, ThrustlerBackendError> {
     let (instance, debug_callback) = create_vulkan_library(
         window.clone(),
@@ -72,7 +135,7 @@ fn create_vulkano_toolkit(
     let surface = create_surface(instance.clone(), window.clone())?;
 
     let (physical_device, queue_family_index) = pick_physical_device_and_queue_family_index(
-        instance.clone(), surface.clone())?;
+        instance.clone(), surface.clone(), &render_settings.adapter)?;
     let (logical_device, queue) = crete_logical_device(
         physical_device.clone(),
         queue_family_index,
@@ -83,39 +146,36 @@ fn create_vulkano_toolkit(
         logical_device.clone(),
         surface.clone(),
         size,
+        render_settings,
     )?;
 
+    let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(logical_device.clone()));
+
     let render_pass = create_render_pass(
         logical_device.clone(),
         swapchain.clone(),
+        pick_sample_count(&physical_device, render_settings),
+        pick_stencil_format(&physical_device)?,
     )?;
 
     let framebuffers = create_framebuffers(
         &swapchain_images,
         render_pass.clone(),
+        memory_allocator.clone(),
     )?;
 
-    let vertex_shader = simple_vertex_shader::load(
-        logical_device.clone()
-    )
-        .attach_printable("Vertex shader loading error")
-        .change_context(ThrustlerBackendError::ShaderError)?;
-
-    let fragment_shader = simple_fragment_shader::load(
-        logical_device.clone()
+    let shader_module = compile_wgsl(
+        logical_device.clone(),
+        include_str!("../../../assets/shaders/wgsl/simple_shader.wgsl"),
     )
-        .attach_printable("Fragment shader loading error")
-        .change_context(ThrustlerBackendError::ShaderError)?;
+        .attach_printable("Built-in shader loading error")?;
 
-    let pipeline = create_pipeline(
+    let pipelines = create_pipelines(
         logical_device.clone(),
-        vertex_shader,
-        fragment_shader,
+        shader_module,
         render_pass.clone(),
-        size,
     )?;
 
-    let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(logical_device.clone()));
     let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
         logical_device.clone(),
         StandardCommandBufferAllocatorCreateInfo::default()),
@@ -126,9 +186,12 @@ fn create_vulkano_toolkit(
         memory_allocator.clone(),
         logical_device.clone(),
         queue.clone(),
-        pipeline.clone(),
-        swapchain.clone(),
-        framebuffers.clone(),
+        pipelines,
+        FrameTargets {
+            swapchain: swapchain.clone(),
+            framebuffers: framebuffers.clone(),
+            frames_in_flight: render_settings.frames_in_flight,
+        },
     );
 
     Ok(VulkanoToolkit {
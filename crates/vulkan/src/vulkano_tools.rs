@@ -1,11 +1,12 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
 use std::sync::Arc;
 
 use error_stack::{Context, Report, Result};
 use error_stack::ResultExt;
-use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use naga::back::spv;
+use naga::front::wgsl;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
 use uuid::Uuid;
 use vulkano::{swapchain, sync, Validated, VulkanError, VulkanLibrary};
 use vulkano::buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer};
@@ -13,13 +14,15 @@ use vulkano::command_buffer::{CommandBuffer, CommandBufferBeginInfo, CommandBuff
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
 use vulkano::device::{Device, DeviceCreateInfo, DeviceExtensions, Queue, QueueCreateInfo, QueueFlags};
 use vulkano::device::physical::{PhysicalDevice, PhysicalDeviceType};
-use vulkano::image::{Image, ImageUsage};
+use vulkano::format::{ClearValue, Format, FormatFeatures};
+use vulkano::image::{Image, ImageAspects, ImageCreateInfo, ImageType, ImageUsage, SampleCount};
 use vulkano::image::view::ImageView;
 use vulkano::instance::{Instance, InstanceCreateFlags, InstanceCreateInfo, InstanceExtensions, LayerProperties};
 use vulkano::instance::debug::{DebugUtilsMessageSeverity, DebugUtilsMessageType, DebugUtilsMessenger, DebugUtilsMessengerCallback, DebugUtilsMessengerCreateInfo};
 use vulkano::memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator};
-use vulkano::pipeline::{GraphicsPipeline, PipelineLayout, PipelineShaderStageCreateInfo};
-use vulkano::pipeline::graphics::color_blend::{ColorBlendAttachmentState, ColorBlendState};
+use vulkano::pipeline::{DynamicState, GraphicsPipeline, PipelineLayout, PipelineShaderStageCreateInfo};
+use vulkano::pipeline::graphics::color_blend::{ColorBlendAttachmentState, ColorBlendState, ColorComponents};
+use vulkano::pipeline::graphics::depth_stencil::{CompareOp, DepthStencilState, StencilFaces, StencilOp, StencilOpState, StencilOps, StencilState};
 use vulkano::pipeline::graphics::GraphicsPipelineCreateInfo;
 use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
 use vulkano::pipeline::graphics::multisample::MultisampleState;
@@ -28,12 +31,16 @@ use vulkano::pipeline::graphics::vertex_input::{Vertex, VertexDefinition};
 use vulkano::pipeline::graphics::viewport::{Viewport, ViewportState};
 use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
 use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass};
-use vulkano::shader::ShaderModule;
-use vulkano::swapchain::{Surface, Swapchain, SwapchainCreateInfo, SwapchainPresentInfo};
+use vulkano::shader::{ShaderModule, ShaderModuleCreateInfo};
+use vulkano::swapchain::{PresentMode, Surface, Swapchain, SwapchainCreateInfo, SwapchainPresentInfo};
 use vulkano::sync::GpuFuture;
 
 use core::{Size};
-use core::game_objects::{GameObject, Vertex as ThrustlerVertex};
+use core::game_objects::{GameObject, StencilMode, Vertex as ThrustlerVertex};
+use core::render::{AdapterKind, AdapterPreference, PresentMode as ThrustlerPresentMode, RenderSettings};
+use core::shader::{Shader, ShaderId};
+use core::stats::RenderStats;
+use core::window::OutputWindow;
 
 #[derive(Debug)]
 pub(crate) enum ThrustlerBackendError {
@@ -61,10 +68,8 @@ impl Display for ThrustlerBackendError {
 
 impl Context for ThrustlerBackendError {}
 
-pub trait VulkanWindow: HasWindowHandle + HasDisplayHandle {}
-
 pub(crate) fn create_vulkan_library(
-    window: Arc<dyn VulkanWindow>,
+    window: Arc<dyn OutputWindow>,
     is_debug: bool,
 ) -> Result<(Arc<Instance>, Option<DebugUtilsMessenger>), ThrustlerBackendError> {
     let required_validation_layers = ["VK_LAYER_KHRONOS_validation"];
@@ -195,7 +200,7 @@ fn hook_up_debug_callback(instance: Arc<Instance>) -> Option<DebugUtilsMessenger
 }
 
 pub(crate) fn create_surface(instance: Arc<Instance>,
-                             window: Arc<dyn VulkanWindow>,
+                             window: Arc<dyn OutputWindow>,
 ) -> Result<Arc<Surface>, ThrustlerBackendError> {
     unsafe {
         Surface::from_window_ref(instance, &window)
@@ -214,6 +219,7 @@ fn device_extensions() -> DeviceExtensions {
 pub(crate) fn pick_physical_device_and_queue_family_index(
     instance: Arc<Instance>,
     surface: Arc<Surface>,
+    adapter: &AdapterPreference,
 ) -> Result<(Arc<PhysicalDevice>, u32), ThrustlerBackendError> {
     instance
         .enumerate_physical_devices()
@@ -236,20 +242,27 @@ pub(crate) fn pick_physical_device_and_queue_family_index(
                         .map(|q| (physical_device, q as u32))
                 })
                 .min_by_key(|(physical_device, _)| {
-                    match physical_device.properties().device_type {
-                        // integral gpu is used here deliberately for developing
-                        PhysicalDeviceType::IntegratedGpu => 0,
-                        /*PhysicalDeviceType::DiscreteGpu => 0,
-                        PhysicalDeviceType::IntegratedGpu => 1,
-                        PhysicalDeviceType::VirtualGpu => 2,
-                        PhysicalDeviceType::Cpu => 3,*/
-                        _ => 4,
-                    }
+                    let properties = physical_device.properties();
+                    adapter.rank(&properties.device_name, to_adapter_kind(properties.device_type))
                 })
                 .ok_or(Report::new(ThrustlerBackendError::AcquisitionError)
                     .attach_printable("Fail to find an eligible physical device")
                 )
         })
+        .inspect(|(physical_device, _)| {
+            let properties = physical_device.properties();
+            println!("Vulkan uses the adapter {} ({:?})", properties.device_name, properties.device_type);
+        })
+}
+
+fn to_adapter_kind(device_type: PhysicalDeviceType) -> AdapterKind {
+    match device_type {
+        PhysicalDeviceType::DiscreteGpu => AdapterKind::Discrete,
+        PhysicalDeviceType::IntegratedGpu => AdapterKind::Integrated,
+        PhysicalDeviceType::VirtualGpu => AdapterKind::Virtual,
+        PhysicalDeviceType::Cpu => AdapterKind::Software,
+        _ => AdapterKind::Other,
+    }
 }
 
 pub(crate) fn crete_logical_device(
@@ -285,6 +298,7 @@ pub(crate) fn create_swapchain(
     device: Arc<Device>,
     surface: Arc<Surface>,
     size: Size,
+    render_settings: &RenderSettings,
 ) -> Result<(Arc<Swapchain>, Vec<Arc<Image>>), ThrustlerBackendError> {
     let (composite_alpha, min_image_count) = physical_device
         .surface_capabilities(&surface, Default::default())
@@ -298,9 +312,24 @@ pub(crate) fn create_swapchain(
                     Report::new(ThrustlerBackendError::AcquisitionError)
                         .attach_printable("Unable to acquire composite alpha")
                 )?;
-            Ok((composite_alpha, capabilities.min_image_count + 1))
+            //one more image than frames in flight, so the next frame isn't blocked by the presented one
+            let image_count = (capabilities.min_image_count + 1).max(render_settings.frames_in_flight.max(1) + 1);
+            let image_count = capabilities.max_image_count.map_or(image_count, |max| image_count.min(max));
+            Ok((composite_alpha, image_count))
         })?;
 
+    let supported_present_modes = physical_device
+        .surface_present_modes(&surface, Default::default())
+        .attach_printable("Unable to acquire present modes")
+        .change_context(ThrustlerBackendError::AcquisitionError)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    let present_mode = render_settings.present_mode
+        .choose(|present_mode| supported_present_modes.contains(&to_vulkan_present_mode(present_mode)));
+    if present_mode != render_settings.present_mode {
+        println!("Present mode {:?} isn't supported, {present_mode:?} is used", render_settings.present_mode);
+    }
+
     let image_format = physical_device
         .surface_formats(&surface, Default::default())
         .attach_printable("Unable to acquire image format")
@@ -315,6 +344,7 @@ pub(crate) fn create_swapchain(
             image_extent: size.into(),
             image_usage: ImageUsage::COLOR_ATTACHMENT, // What the images are going to be used for
             composite_alpha,
+            present_mode: to_vulkan_present_mode(present_mode),
             ..Default::default()
         },
     )
@@ -322,18 +352,71 @@ pub(crate) fn create_swapchain(
         .change_context(ThrustlerBackendError::CreationError)
 }
 
+fn to_vulkan_present_mode(present_mode: ThrustlerPresentMode) -> PresentMode {
+    match present_mode {
+        ThrustlerPresentMode::Fifo => PresentMode::Fifo,
+        ThrustlerPresentMode::FifoRelaxed => PresentMode::FifoRelaxed,
+        ThrustlerPresentMode::Mailbox => PresentMode::Mailbox,
+        ThrustlerPresentMode::Immediate => PresentMode::Immediate,
+    }
+}
+
+/// The sample count of the settings or the highest one which the device supports for color and stencil attachments.
+pub(crate) fn pick_sample_count(physical_device: &PhysicalDevice, render_settings: &RenderSettings) -> SampleCount {
+    let properties = physical_device.properties();
+    let supported_sample_counts = properties.framebuffer_color_sample_counts & properties.framebuffer_stencil_sample_counts;
+    let sample_count = render_settings.supported_sample_count(|sample_count| {
+        SampleCount::try_from(sample_count).is_ok_and(|sample_count| supported_sample_counts.contains_enum(sample_count))
+    });
+    if sample_count != render_settings.sample_count {
+        println!("MSAA with {} samples isn't supported, {sample_count} samples are used", render_settings.sample_count);
+    }
+    SampleCount::try_from(sample_count).unwrap_or(SampleCount::Sample1)
+}
+
+/// The first format with a stencil which can be used as an attachment, pure stencil formats go first.
+pub(crate) fn pick_stencil_format(physical_device: &PhysicalDevice) -> Result<Format, ThrustlerBackendError> {
+    [Format::S8_UINT, Format::D24_UNORM_S8_UINT, Format::D32_SFLOAT_S8_UINT, Format::D16_UNORM_S8_UINT]
+        .into_iter()
+        .find(|format| {
+            physical_device.format_properties(*format)
+                .map(|properties| properties.optimal_tiling_features.intersects(FormatFeatures::DEPTH_STENCIL_ATTACHMENT))
+                .unwrap_or(false)
+        })
+        .ok_or(
+            Report::new(ThrustlerBackendError::AcquisitionError)
+                .attach_printable("Fail to find a stencil format")
+        )
+}
+
 pub(crate) fn create_framebuffers(
     images: &[Arc<Image>],
     render_pass: Arc<RenderPass>,
+    memory_allocator: Arc<StandardMemoryAllocator>,
 ) -> Result<Vec<Arc<Framebuffer>>, ThrustlerBackendError> {
+    let samples = render_pass.attachments()[0].samples;
+    let stencil_format = render_pass.attachments().iter()
+        .map(|attachment| attachment.format)
+        .find(|format| format.aspects().intersects(ImageAspects::STENCIL));
     images
         .iter()
         .map(|image| {
             let view = ImageView::new_default(image.clone()).unwrap();
+            let mut attachments = vec![];
+            //with MSAA every swapchain image gets its multisampled image which is resolved into it
+            if samples != SampleCount::Sample1 {
+                let usage = ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT;
+                attachments.push(create_attachment_view(memory_allocator.clone(), image, image.format(), usage, samples)?);
+            }
+            attachments.push(view);
+            if let Some(stencil_format) = stencil_format {
+                let usage = ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT;
+                attachments.push(create_attachment_view(memory_allocator.clone(), image, stencil_format, usage, samples)?);
+            }
             Framebuffer::new(
                 render_pass.clone(),
                 FramebufferCreateInfo {
-                    attachments: vec![view],
+                    attachments,
                     ..Default::default()
                 },
             )
@@ -343,7 +426,72 @@ pub(crate) fn create_framebuffers(
         .collect()
 }
 
-pub(crate) fn create_render_pass(device: Arc<Device>, swapchain: Arc<Swapchain>) -> Result<Arc<RenderPass>, ThrustlerBackendError> {
+/// An image of the same size as the swapchain image which lives only during the render pass.
+fn create_attachment_view(
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    swapchain_image: &Image,
+    format: Format,
+    usage: ImageUsage,
+    samples: SampleCount,
+) -> Result<Arc<ImageView>, ThrustlerBackendError> {
+    let image = Image::new(
+        memory_allocator,
+        ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format,
+            extent: swapchain_image.extent(),
+            usage,
+            samples,
+            ..Default::default()
+        },
+        AllocationCreateInfo::default(),
+    )
+        .attach_printable_lazy(|| format!("Can't create {format:?} attachment image"))
+        .change_context(ThrustlerBackendError::AllocationError)?;
+    ImageView::new_default(image)
+        .attach_printable_lazy(|| format!("Can't create {format:?} attachment image view"))
+        .change_context(ThrustlerBackendError::CreationError)
+}
+
+pub(crate) fn create_render_pass(
+    device: Arc<Device>,
+    swapchain: Arc<Swapchain>,
+    samples: SampleCount,
+    stencil_format: Format,
+) -> Result<Arc<RenderPass>, ThrustlerBackendError> {
+    if samples != SampleCount::Sample1 {
+        return vulkano::single_pass_renderpass!(
+            device,
+            attachments: {
+                multisampled: {
+                    format: swapchain.image_format(),
+                    samples: samples as u32,
+                    load_op: Clear,
+                    store_op: DontCare,
+                },
+                color: {
+                    format: swapchain.image_format(),
+                    samples: 1,
+                    load_op: DontCare,
+                    store_op: Store,
+                },
+                stencil: {
+                    format: stencil_format,
+                    samples: samples as u32,
+                    load_op: Clear,
+                    store_op: DontCare,
+                },
+            },
+            pass: {
+                color: [multisampled],
+                color_resolve: [color],
+                depth_stencil: {stencil},
+            },
+        )
+            .attach_printable("Can't create multisampled render pass")
+            .change_context(ThrustlerBackendError::CreationError);
+    }
+
     vulkano::single_pass_renderpass!(
         device,
         attachments: {
@@ -353,25 +501,87 @@ pub(crate) fn create_render_pass(device: Arc<Device>, swapchain: Arc<Swapchain>)
                 load_op: Clear,
                 store_op: Store,
             },
+            stencil: {
+                format: stencil_format,
+                samples: 1,
+                load_op: Clear,
+                store_op: DontCare,
+            },
         },
         pass: {
             color: [color],
-            depth_stencil: {},
+            depth_stencil: {stencil},
         },
     )
         .attach_printable("Can't create pipeline")
         .change_context(ThrustlerBackendError::CreationError)
 }
 
-pub(crate) fn create_pipeline(
+/// Clear values for every attachment of the framebuffer, resolved attachments aren't cleared.
+fn clear_values(framebuffer: &Framebuffer) -> Vec<Option<ClearValue>> {
+    framebuffer.attachments().iter()
+        .enumerate()
+        .map(|(index, attachment)| {
+            let aspects = attachment.format().aspects();
+            if aspects.intersects(ImageAspects::STENCIL) {
+                //combined formats are cleared with the depth as well
+                Some(if aspects.intersects(ImageAspects::DEPTH) { ClearValue::DepthStencil((1.0, 0)) } else { ClearValue::Stencil(0) })
+            } else if index == 0 {
+                Some([0.1, 0.1, 0.1, 1.0].into())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// A pipeline for every stencil mode, the stencil value is set for every game object.
+pub(crate) struct StencilPipelines {
+    none: Arc<GraphicsPipeline>,
+    mask: Arc<GraphicsPipeline>,
+    inside: Arc<GraphicsPipeline>,
+    outside: Arc<GraphicsPipeline>,
+}
+
+impl StencilPipelines {
+    fn get(&self, stencil: StencilMode) -> Arc<GraphicsPipeline> {
+        match stencil {
+            StencilMode::None => self.none.clone(),
+            StencilMode::Mask(_) => self.mask.clone(),
+            StencilMode::Inside(_) => self.inside.clone(),
+            StencilMode::Outside(_) => self.outside.clone(),
+        }
+    }
+}
+
+pub(crate) fn create_pipelines(
     device: Arc<Device>,
-    vs: Arc<ShaderModule>,
-    fs: Arc<ShaderModule>,
+    shader_module: Arc<ShaderModule>,
     render_pass: Arc<RenderPass>,
-    size: Size,
+) -> Result<StencilPipelines, ThrustlerBackendError> {
+    let pipeline_for = |stencil| create_pipeline(device.clone(), shader_module.clone(), render_pass.clone(), stencil);
+    Ok(StencilPipelines {
+        none: pipeline_for(StencilMode::None)?,
+        mask: pipeline_for(StencilMode::Mask(0))?,
+        inside: pipeline_for(StencilMode::Inside(0))?,
+        outside: pipeline_for(StencilMode::Outside(0))?,
+    })
+}
+
+fn create_pipeline(
+    device: Arc<Device>,
+    shader_module: Arc<ShaderModule>,
+    render_pass: Arc<RenderPass>,
+    stencil: StencilMode,
 ) -> Result<Arc<GraphicsPipeline>, ThrustlerBackendError> {
-    let vs = vs.entry_point("main").unwrap();
-    let fs = fs.entry_point("main").unwrap();
+    let vs = shader_module.entry_point("vs_main").ok_or(
+        Report::new(ThrustlerBackendError::ShaderError)
+            .attach_printable("Shader has no vs_main entry point")
+    )?;
+    let fs = shader_module.entry_point("fs_main").ok_or(
+        Report::new(ThrustlerBackendError::ShaderError)
+            .attach_printable("Shader has no fs_main entry point")
+    )?;
 
     let stages = [
         PipelineShaderStageCreateInfo::new(vs.clone()),
@@ -398,10 +608,24 @@ pub(crate) fn create_pipeline(
             .attach_printable("Can't get subpass from render pass")
     )?;
 
-    let viewport = Viewport {
-        offset: [0.0, 0.0],
-        extent: size.into(),
-        depth_range: 0.0..=1.0,
+    let (compare_op, pass_op) = match stencil {
+        StencilMode::None => (CompareOp::Always, StencilOp::Keep),
+        StencilMode::Mask(_) => (CompareOp::Always, StencilOp::Replace),
+        StencilMode::Inside(_) => (CompareOp::Equal, StencilOp::Keep),
+        StencilMode::Outside(_) => (CompareOp::NotEqual, StencilOp::Keep),
+    };
+    let is_mask = matches!(stencil, StencilMode::Mask(_));
+    let stencil_op_state = StencilOpState {
+        ops: StencilOps {
+            fail_op: StencilOp::Keep,
+            pass_op,
+            depth_fail_op: StencilOp::Keep,
+            compare_op,
+        },
+        compare_mask: 0xff,
+        write_mask: if is_mask { 0xff } else { 0 },
+        //the reference is dynamic, it's set for every game object
+        reference: 0,
     };
 
     GraphicsPipeline::new(
@@ -411,15 +635,28 @@ pub(crate) fn create_pipeline(
             stages: stages.into_iter().collect(),
             vertex_input_state: Some(vertex_input_state),
             input_assembly_state: Some(InputAssemblyState::default()),
-            viewport_state: Some(ViewportState {
-                viewports: [viewport].into_iter().collect(),
+            //the viewport is set for every frame, so the pipeline survives resizing
+            viewport_state: Some(ViewportState::default()),
+            dynamic_state: [DynamicState::Viewport, DynamicState::StencilReference].into_iter().collect(),
+            rasterization_state: Some(RasterizationState::default()),
+            multisample_state: Some(MultisampleState {
+                rasterization_samples: subpass.num_samples().unwrap_or(SampleCount::Sample1),
+                ..Default::default()
+            }),
+            depth_stencil_state: Some(DepthStencilState {
+                stencil: Some(StencilState {
+                    front: stencil_op_state,
+                    back: stencil_op_state,
+                }),
                 ..Default::default()
             }),
-            rasterization_state: Some(RasterizationState::default()),
-            multisample_state: Some(MultisampleState::default()),
             color_blend_state: Some(ColorBlendState::with_attachment_states(
                 subpass.num_color_attachments(),
-                ColorBlendAttachmentState::default(),
+                ColorBlendAttachmentState {
+                    //masks only mark the stencil buffer
+                    color_write_mask: if is_mask { ColorComponents::empty() } else { ColorComponents::all() },
+                    ..Default::default()
+                },
             )),
             subpass: Some(subpass.into()),
             ..GraphicsPipelineCreateInfo::layout(layout)
@@ -429,16 +666,55 @@ pub(crate) fn create_pipeline(
         .change_context(ThrustlerBackendError::CreationError)
 }
 
+/// Compiles WGSL with both stages into a single SPIR-V module and loads it.
+pub(crate) fn compile_wgsl(device: Arc<Device>, source: &str) -> Result<Arc<ShaderModule>, ThrustlerBackendError> {
+    let module = wgsl::parse_str(source)
+        .map_err(|err| Report::new(ThrustlerBackendError::ShaderError).attach_printable(err.emit_to_string(source)))?;
+    let module_info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|err| Report::new(ThrustlerBackendError::ShaderError).attach_printable(err.emit_to_string(source)))?;
+
+    //the default options flip the y axis, it's kept pointing down in the Vulkan clip space like with GLSL shaders
+    let options = spv::Options {
+        flags: spv::WriterFlags::LABEL_VARYINGS | spv::WriterFlags::CLAMP_FRAG_DEPTH,
+        ..Default::default()
+    };
+    let words = spv::write_vec(&module, &module_info, &options, None)
+        .attach_printable("Can't write SPIR-V")
+        .change_context(ThrustlerBackendError::ShaderError)?;
+
+    unsafe { ShaderModule::new(device, ShaderModuleCreateInfo::new(&words)) }
+        .attach_printable("Can't load SPIR-V")
+        .change_context(ThrustlerBackendError::ShaderError)
+}
+
+//buffers are cached per scene, they are keyed by the index of the scene in the window and the id of the object
+type SubbufferCache = HashMap<(usize, Uuid), (Subbuffer<[VulkanVertex]>, bool)>;
+
 pub(crate) struct CommandBufferExecutor {
-    subbuffer_cache: HashMap<Uuid, (Subbuffer<[VulkanVertex]>, bool)>,
+    subbuffer_cache: SubbufferCache,
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
     standard_memory_allocator: Arc<StandardMemoryAllocator>,
     queue: Arc<Queue>,
-    pipeline: Arc<GraphicsPipeline>,
+    pipelines: StencilPipelines,
+    //pipelines of shaders supplied at runtime, they are created when an object with the shader is drawn first
+    custom_pipelines: HashMap<ShaderId, StencilPipelines>,
+    //shaders which can't be compiled, their objects are drawn with the built-in pipelines until the shader is reloaded
+    failed_shaders: HashSet<ShaderId>,
     logical_device: Arc<Device>,
     swapchain: Arc<Swapchain>,
     framebuffers: Vec<Arc<Framebuffer>>,
-    last_frame_fence: RefCell<Option<Box<dyn GpuFuture>>>,
+    //fences of the frames which may still be drawn by the GPU, one per frame in flight
+    frame_fences: Vec<Option<Box<dyn GpuFuture>>>,
+    frame_index: usize,
+    render_stats: RenderStats,
+}
+
+/// Swapchain images which frames are drawn into and how many frames can be drawn by the GPU at once.
+pub(crate) struct FrameTargets {
+    pub swapchain: Arc<Swapchain>,
+    pub framebuffers: Vec<Arc<Framebuffer>>,
+    pub frames_in_flight: u32,
 }
 
 pub enum BufferExecutorResult {
@@ -453,43 +729,53 @@ impl CommandBufferExecutor {
         standard_memory_allocator: Arc<StandardMemoryAllocator>,
         logical_device: Arc<Device>,
         queue: Arc<Queue>,
-        pipeline: Arc<GraphicsPipeline>,
-        swapchain: Arc<Swapchain>,
-        framebuffers: Vec<Arc<Framebuffer>>,
+        pipelines: StencilPipelines,
+        frame_targets: FrameTargets,
     ) -> Self {
-        let last_frame_fence = RefCell::new(Some(sync::now(logical_device.clone()).boxed()));
+        let FrameTargets { swapchain, framebuffers, frames_in_flight } = frame_targets;
+        let frame_fences = (0..frames_in_flight.max(1)).map(|_| None).collect();
         Self {
             command_buffer_allocator,
             standard_memory_allocator,
             queue,
-            pipeline,
+            pipelines,
+            custom_pipelines: HashMap::new(),
+            failed_shaders: HashSet::new(),
             logical_device,
             swapchain,
             framebuffers,
-            last_frame_fence,
+            frame_fences,
+            frame_index: 0,
             subbuffer_cache: HashMap::new(),
+            render_stats: RenderStats::default(),
         }
     }
 
-    pub fn execute_buffer(&mut self, game_objects: &Vec<GameObject>) -> BufferExecutorResult {
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats
+    }
+
+    /// Draws objects of all scenes into the next swapchain image, scenes are drawn over each other in their order.
+    pub fn execute_buffer(&mut self, scene_objects: &[&Vec<GameObject>]) -> BufferExecutorResult {
+        //the slot was taken `frames_in_flight` frames ago, dropping its fence waits until that frame is drawn
+        drop(self.frame_fences[self.frame_index].take());
+        self.frame_fences.iter_mut().flatten().for_each(|fence| fence.cleanup_finished());
+
         swapchain::acquire_next_image(self.swapchain.clone(), None)
-            .map_err(|_| {
-                BufferExecutorResult::Fail
+            .map_err(Validated::unwrap)
+            .map_err(|err| match err {
+                //the window could be resized after the swapchain was recreated
+                VulkanError::OutOfDate => BufferExecutorResult::Recreate,
+                _ => BufferExecutorResult::Fail
             })
             .and_then(|(image_index, suboptimal, swapchain_future)| {
                 if suboptimal {
-                    {
-                        let mut mut_last_frame_fence = self.last_frame_fence.borrow_mut();
-                        mut_last_frame_fence.as_mut().unwrap().cleanup_finished();
-                    }
                     Ok(BufferExecutorResult::Recreate)
                 } else {
-                    self.create_command_buffer(self.framebuffers[image_index as usize].clone(), game_objects)
+                    self.create_command_buffer(self.framebuffers[image_index as usize].clone(), scene_objects)
                         .map_err(|_| BufferExecutorResult::Fail)
                         .and_then(|command_buffer| {
-                            self.last_frame_fence
-                                .take()
-                                .unwrap_or(sync::now(self.logical_device.clone()).boxed())
+                            sync::now(self.logical_device.clone())
                                 .join(swapchain_future)
                                 .then_execute(self.queue.clone(), command_buffer)
                                 .map_err(|_| BufferExecutorResult::Fail)
@@ -500,22 +786,14 @@ impl CommandBufferExecutor {
                                             SwapchainPresentInfo::swapchain_image_index(self.swapchain.clone(), image_index),
                                         )
                                         .then_signal_fence_and_flush()
-                                        .map(|_future| {
-                                            {
-                                                let mut mut_last_frame_fence = self.last_frame_fence.borrow_mut();
-                                                mut_last_frame_fence.replace(sync::now(self.logical_device.clone()).boxed());
-                                            }
+                                        .map(|future| {
+                                            self.frame_fences[self.frame_index] = Some(future.boxed());
+                                            self.frame_index = (self.frame_index + 1) % self.frame_fences.len();
                                             BufferExecutorResult::Done
                                         })
                                         .map_err(Validated::unwrap)
                                         .map_err(|err| match err {
-                                            VulkanError::OutOfDate => {
-                                                {
-                                                    let mut mut_last_frame_fence = self.last_frame_fence.borrow_mut();
-                                                    mut_last_frame_fence.as_mut().unwrap().cleanup_finished();
-                                                }
-                                                BufferExecutorResult::Recreate
-                                            }
+                                            VulkanError::OutOfDate => BufferExecutorResult::Recreate,
                                             _ => BufferExecutorResult::Fail
                                         })
                                 })
@@ -524,7 +802,88 @@ impl CommandBufferExecutor {
             })
             .unwrap_or_else(|err| err)
     }
-    fn create_command_buffer(&mut self, framebuffer: Arc<Framebuffer>, game_objects: &Vec<GameObject>) -> Result<Arc<CommandBuffer>, ThrustlerBackendError> {
+
+    /// Recreates the swapchain with the same parameters, it's required when the swapchain is out of date.
+    pub fn recreate_swapchain(&mut self) -> Result<(), ThrustlerBackendError> {
+        self.rebuild_swapchain(self.swapchain.create_info())
+    }
+
+    /// Recreates the swapchain with images of the new size.
+    pub fn resize(&mut self, size: Size) -> Result<(), ThrustlerBackendError> {
+        self.rebuild_swapchain(SwapchainCreateInfo {
+            image_extent: size.into(),
+            ..self.swapchain.create_info()
+        })
+    }
+
+    fn rebuild_swapchain(&mut self, create_info: SwapchainCreateInfo) -> Result<(), ThrustlerBackendError> {
+        let (swapchain, images) = self.swapchain
+            .recreate(create_info)
+            .attach_printable("Can't recreate swapchain")
+            .change_context(ThrustlerBackendError::CreationError)?;
+
+        let render_pass = self.render_pass()?;
+
+        self.framebuffers = create_framebuffers(&images, render_pass, self.standard_memory_allocator.clone())?;
+        self.swapchain = swapchain;
+        Ok(())
+    }
+
+    fn render_pass(&self) -> Result<Arc<RenderPass>, ThrustlerBackendError> {
+        self.framebuffers.first()
+            .map(|framebuffer| framebuffer.render_pass().clone())
+            .ok_or(
+                Report::new(ThrustlerBackendError::AcquisitionError)
+                    .attach_printable("Can't get render pass from framebuffers")
+            )
+    }
+
+    /// Creates pipelines of shaders which haven't been drawn yet.
+    /// A shader which fails is reported once and its objects are drawn with the built-in pipelines.
+    pub fn prepare_pipelines(&mut self, scene_objects: &[&Vec<GameObject>]) {
+        let game_objects = scene_objects.iter().flat_map(|game_objects| game_objects.iter());
+        for shader in game_objects.filter_map(|game_object| game_object.shader.as_ref()) {
+            if self.custom_pipelines.contains_key(&shader.id()) || self.failed_shaders.contains(&shader.id()) {
+                continue;
+            }
+            match self.create_custom_pipelines(shader) {
+                Ok(pipelines) => {
+                    self.custom_pipelines.insert(shader.id(), pipelines);
+                }
+                Err(report) => {
+                    println!("Shader {:?} can't be used, the built-in one is used instead: {report:?}", shader.id());
+                    self.failed_shaders.insert(shader.id());
+                }
+            }
+        }
+    }
+
+    /// Replaces pipelines of the shader, the previous ones are kept if the new source fails.
+    pub fn reload_shader(&mut self, shader: &Shader) -> Result<(), ThrustlerBackendError> {
+        let pipelines = self.create_custom_pipelines(shader)?;
+        if shader.id() == ShaderId::BUILT_IN {
+            self.pipelines = pipelines;
+        } else {
+            self.failed_shaders.remove(&shader.id());
+            self.custom_pipelines.insert(shader.id(), pipelines);
+        }
+        Ok(())
+    }
+
+    fn create_custom_pipelines(&self, shader: &Shader) -> Result<StencilPipelines, ThrustlerBackendError> {
+        let shader_module = compile_wgsl(self.logical_device.clone(), shader.wgsl())
+            .attach_printable("Shader compilation error")?;
+        create_pipelines(self.logical_device.clone(), shader_module, self.render_pass()?)
+    }
+
+    fn pipeline(&self, game_object: &GameObject) -> Arc<GraphicsPipeline> {
+        game_object.shader.as_ref()
+            .and_then(|shader| self.custom_pipelines.get(&shader.id()))
+            .unwrap_or(&self.pipelines)
+            .get(game_object.stencil)
+    }
+
+    fn create_command_buffer(&mut self, framebuffer: Arc<Framebuffer>, scene_objects: &[&Vec<GameObject>]) -> Result<Arc<CommandBuffer>, ThrustlerBackendError> {
         let builder = RecordingCommandBuffer::new(
             self.command_buffer_allocator.clone(),
             self.queue.clone().queue_family_index(),
@@ -540,8 +899,7 @@ impl CommandBufferExecutor {
         self.fill_render_pass(
             builder,
             framebuffer.clone(),
-            self.pipeline.clone(),
-            game_objects,
+            scene_objects,
         )
             ?.end()
             .attach_printable("Render pass stuffing is failed")
@@ -556,7 +914,7 @@ impl CommandBufferExecutor {
     }
 
     fn delete_all_unused_buffers(&mut self) {
-        let dead_buffer_uuids: Vec<_> = self.subbuffer_cache.iter().filter_map(|bucket| {
+        let dead_buffer_keys: Vec<_> = self.subbuffer_cache.iter().filter_map(|bucket| {
             if !bucket.1.1 {
                 Some(*bucket.0)
             } else {
@@ -564,18 +922,20 @@ impl CommandBufferExecutor {
             }
         }).collect();
 
-        for dead_buffer_uuid in dead_buffer_uuids {
-            self.subbuffer_cache.remove(&dead_buffer_uuid);
+        for dead_buffer_key in dead_buffer_keys {
+            self.subbuffer_cache.remove(&dead_buffer_key);
         }
     }
 
-    fn get_subbuffer_for_game_object(&mut self, game_object: &GameObject) -> Result<Subbuffer<[VulkanVertex]>, ThrustlerBackendError> {
-        let subbuffer = if let Some(subbuffer) = self.subbuffer_cache.get_mut(&game_object.id) {
+    fn get_subbuffer_for_game_object(&mut self, scene_index: usize, game_object: &GameObject) -> Result<Subbuffer<[VulkanVertex]>, ThrustlerBackendError> {
+        let key = (scene_index, game_object.id);
+        let subbuffer = if let Some(subbuffer) = self.subbuffer_cache.get_mut(&key) {
             subbuffer.1 = true;
             subbuffer.0.clone()
         } else {
             let vertices = self.create_vertex_buffer(game_object)?;
-            self.subbuffer_cache.insert(game_object.id, (vertices.clone(), true));
+            self.render_stats.uploaded_bytes += vertices.size();
+            self.subbuffer_cache.insert(key, (vertices.clone(), true));
             vertices
         };
 
@@ -606,13 +966,12 @@ impl CommandBufferExecutor {
         &mut self,
         mut builder: RecordingCommandBuffer,
         framebuffer: Arc<Framebuffer>,
-        pipeline: Arc<GraphicsPipeline>,
-        game_objects: &Vec<GameObject>,
+        scene_objects: &[&Vec<GameObject>],
     ) -> Result<RecordingCommandBuffer, ThrustlerBackendError> {
         builder
             .begin_render_pass(
                 RenderPassBeginInfo {
-                    clear_values: vec![Some([0.1, 0.1, 0.1, 1.0].into())],
+                    clear_values: clear_values(&framebuffer),
                     ..RenderPassBeginInfo::framebuffer(framebuffer.clone())
                 },
                 SubpassBeginInfo {
@@ -622,27 +981,49 @@ impl CommandBufferExecutor {
             )
             .attach_printable("Begin render pass is failed")
             .change_context(ThrustlerBackendError::GraphicalApiError)?
-            .bind_pipeline_graphics(pipeline.clone())
+            .bind_pipeline_graphics(self.pipelines.get(StencilMode::None))
             .attach_printable("Bind pipeline is failed")
             .change_context(ThrustlerBackendError::GraphicalApiError)?;
 
+        let [width, height] = framebuffer.extent();
+        let viewport = Viewport {
+            offset: [0.0, 0.0],
+            extent: [width as f32, height as f32],
+            depth_range: 0.0..=1.0,
+        };
+        builder.set_viewport(0, [viewport].into_iter().collect())
+            .attach_printable("Set viewport is failed")
+            .change_context(ThrustlerBackendError::GraphicalApiError)?;
+
 
+        self.render_stats = RenderStats::default();
         //Mark all existing subbuffers as unused
         self.mark_buffers_as_unused();
-        for game_object in game_objects {
-            let vertices = self.get_subbuffer_for_game_object(game_object)?;
+        let game_objects = scene_objects.iter()
+            .enumerate()
+            .flat_map(|(scene_index, game_objects)| game_objects.iter().map(move |game_object| (scene_index, game_object)));
+        for (scene_index, game_object) in game_objects {
+            let vertices = self.get_subbuffer_for_game_object(scene_index, game_object)?;
             let vertices_count = vertices.len() as u32;
 
-            builder.bind_vertex_buffers(0, vertices)
+            builder.bind_pipeline_graphics(self.pipeline(game_object))
+                .attach_printable("Bind pipeline is failed")
+                .change_context(ThrustlerBackendError::GraphicalApiError)?
+                .set_stencil_reference(StencilFaces::FrontAndBack, game_object.stencil.reference())
+                .attach_printable("Set stencil reference is failed")
+                .change_context(ThrustlerBackendError::GraphicalApiError)?
+                .bind_vertex_buffers(0, vertices)
                 .attach_printable("Bind vertex buffer is failed")
                 .change_context(ThrustlerBackendError::GraphicalApiError)?;
 
             unsafe { builder.draw(vertices_count, 1, 0, 0) }
                 .attach_printable("Draw is failed")
                 .change_context(ThrustlerBackendError::GraphicalApiError)?;
+            self.render_stats.draw_calls += 1;
         }
         //Delete all subbuffers which weren't used
         self.delete_all_unused_buffers();
+        self.render_stats.cached_buffers = self.subbuffer_cache.len();
 
         builder.end_render_pass(SubpassEndInfo::default())
             .attach_printable("End render pass is failed")
@@ -1,12 +1,17 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use error_stack::{Result, ResultExt};
+use error_stack::{Report, Result, ResultExt};
 use pollster::FutureExt;
-use wgpu::{Adapter, Instance, InstanceDescriptor, WindowHandle};
+use wgpu::{Adapter, Instance, InstanceDescriptor};
 
 use core::{Size, ThrustlerBackend};
 use core::error::ThrustlerError;
 use core::game_objects::Scene;
+use core::render::RenderSettings;
+use core::shader::{Shader, ShaderId};
+use core::stats::RenderStats;
+use core::window::OutputWindow;
 
 use wgpu_tools::*;
 
@@ -16,6 +21,9 @@ pub struct WgpuBackend {
     instance: Instance,
     toolkit: Option<WgpuToolkit>,
     screen_size: Size,
+    render_settings: RenderSettings,
+    //the toolkit is dropped with the surface, so reloaded shaders are applied again when it's created
+    reloaded_shaders: HashMap<ShaderId, Shader>,
 }
 
 struct WgpuToolkit {
@@ -24,7 +32,7 @@ struct WgpuToolkit {
 }
 
 impl WgpuBackend {
-    pub fn new(screen_size: Size) -> Self {
+    pub fn new(screen_size: Size, render_settings: RenderSettings) -> Self {
         let instance = Instance::new(InstanceDescriptor {
             #[cfg(target_arch = "macos")]
             backends: Backends::METAL,
@@ -35,25 +43,36 @@ impl WgpuBackend {
             instance,
             toolkit: None,
             screen_size,
+            render_settings,
+            reloaded_shaders: HashMap::new(),
         }
     }
 
-    fn get_toolkit(&mut self) -> &mut WgpuToolkit {
-        self.toolkit.as_mut().unwrap()
+    fn get_toolkit(&mut self) -> Result<&mut WgpuToolkit, ThrustlerError> {
+        self.toolkit.as_mut()
+            .ok_or(Report::new(ThrustlerError::GraphicalBackendError))
+            .attach_printable("Wgpu backend isn't initialized")
     }
 
     pub fn init(&mut self,
-                window: Arc<dyn WgpuWindow>,
+                window: Arc<dyn OutputWindow>,
     ) -> Result<(), ThrustlerError> {
         let surface = create_surface(&self.instance, window.clone())?;
-        let adapter = create_adapter(&self.instance, &surface)?;
+        let adapter = create_adapter(&self.instance, &surface, &self.render_settings.adapter)?;
         let (device, queue) = pick_device_and_queue(&adapter)?;
-        let config = create_surface_config(self.screen_size, &surface, &adapter)?;
-        let render_pipeline = create_render_pipeline(&device, &config);
+        let config = create_surface_config(self.screen_size, &surface, &adapter, &self.render_settings)?;
+        let sample_count = pick_sample_count(&adapter, &config, &self.render_settings);
+        let render_pipelines = create_default_render_pipelines(&device, &config, sample_count);
 
         surface.configure(&device, &config);
 
-        let command_buffer_executor = CommandBufferExecutor::new(surface, device, queue, render_pipeline);
+        let mut command_buffer_executor = CommandBufferExecutor::new(surface, config, device, queue, render_pipelines, sample_count);
+        for shader in self.reloaded_shaders.values() {
+            //the surface is still usable with the shaders compiled in
+            if let Err(report) = command_buffer_executor.reload_shader(shader) {
+                println!("Reloaded shader {:?} can't be applied again: {report:?}", shader.id());
+            }
+        }
         let toolkit = WgpuToolkit {
             adapter,
             command_buffer_executor,
@@ -65,11 +84,42 @@ impl WgpuBackend {
     }
 }
 
-pub trait WgpuWindow: WindowHandle {}
-
 impl ThrustlerBackend for WgpuBackend {
-    fn draw_scene(&mut self, scene: &Box<dyn Scene>) {
-        let toolkit = self.get_toolkit();
-        toolkit.command_buffer_executor.execute_buffer(scene.get_scene_objects());
+    fn draw_scenes(&mut self, scenes: &[&dyn Scene]) -> Result<(), ThrustlerError> {
+        let toolkit = self.get_toolkit()?;
+        let scene_objects = scenes.iter().map(|scene| scene.get_scene_objects()).collect::<Vec<_>>();
+        toolkit.command_buffer_executor.execute_buffer(&scene_objects)
+    }
+
+    fn render_stats(&self) -> RenderStats {
+        self.toolkit.as_ref()
+            .map(|toolkit| toolkit.command_buffer_executor.render_stats())
+            .unwrap_or_default()
+    }
+
+    fn resize(&mut self, size: Size) -> Result<(), ThrustlerError> {
+        self.screen_size = size;
+        //the toolkit takes the size when it's initialized
+        if let Some(toolkit) = self.toolkit.as_mut() {
+            toolkit.command_buffer_executor.resize(size);
+        }
+        Ok(())
+    }
+
+    fn release_surface(&mut self) {
+        //the surface keeps the window alive, so it's created again by `init`
+        self.toolkit = None;
+    }
+
+    fn is_y_axis_up(&self) -> bool {
+        true
+    }
+
+    fn reload_shader(&mut self, shader: &Shader) -> Result<(), ThrustlerError> {
+        if let Some(toolkit) = self.toolkit.as_mut() {
+            toolkit.command_buffer_executor.reload_shader(shader)?;
+        }
+        self.reloaded_shaders.insert(shader.id(), shader.clone());
+        Ok(())
     }
 }
@@ -1,41 +1,64 @@
 use std::cell::{Cell, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::num::Wrapping;
 use std::ops::Deref;
 use std::rc::Rc;
 use std::sync::Arc;
 use bytemuck::{Pod, Zeroable};
-use error_stack::ResultExt;
-use wgpu::{Adapter, Backends, BlendState, Buffer, BufferAddress, BufferSlice, BufferUsages, Color, ColorTargetState, ColorWrites, CommandBuffer, CommandEncoderDescriptor, CompositeAlphaMode, Device, DeviceDescriptor, Face, Features, FragmentState, FrontFace, include_wgsl, Instance, Limits, LoadOp, MultisampleState, Operations, PipelineLayoutDescriptor, PolygonMode, PresentMode, PrimitiveState, PrimitiveTopology, Queue, RenderPass, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, StoreOp, Surface, SurfaceConfiguration, SurfaceTexture, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState, VertexStepMode};
+use error_stack::{Report, ResultExt};
+use wgpu::{Adapter, Backends, BlendState, Buffer, BufferAddress, BufferSlice, BufferUsages, Color, ColorTargetState, ColorWrites, CommandBuffer, CommandEncoderDescriptor, CompareFunction, CompositeAlphaMode, DepthBiasState, DepthStencilState, Device, DeviceDescriptor, DeviceType, ErrorFilter, Extent3d, Face, Features, FragmentState, FrontFace, include_wgsl, Instance, Limits, LoadOp, MultisampleState, Operations, PipelineLayout, PipelineLayoutDescriptor, PolygonMode, PresentMode, PrimitiveState, PrimitiveTopology, Queue, RenderPass, RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, StencilFaceState, StencilOperation, StencilState, StoreOp, Surface, SurfaceConfiguration, SurfaceError, SurfaceTexture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState, VertexStepMode};
 use core::error::ThrustlerError;
 use error_stack::Result;
 use pollster::FutureExt;
 use uuid::Uuid;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use core::Size;
-use core::game_objects::{GameObject, Vertex};
-use crate::WgpuWindow;
+use core::game_objects::{GameObject, StencilMode, Vertex};
+use core::shader::{Shader, ShaderId};
+use core::render::{AdapterKind, AdapterPreference, PresentMode as ThrustlerPresentMode, RenderSettings};
+use core::stats::RenderStats;
+use core::window::OutputWindow;
 
-pub(crate) fn create_surface(instance: &Instance, window: Arc<dyn WgpuWindow>) -> Result<Surface<'static>, ThrustlerError> {
+pub(crate) fn create_surface(instance: &Instance, window: Arc<dyn OutputWindow>) -> Result<Surface<'static>, ThrustlerError> {
     instance.create_surface(window)
         .attach_printable("Can't create wgpu surface")
         .change_context(ThrustlerError::GraphicalBackendError)
 }
 
-pub(crate) fn create_adapter(instance: &Instance, surface: &Surface<'static>) -> Result<Adapter, ThrustlerError> {
-    instance
+const STENCIL_FORMAT: TextureFormat = TextureFormat::Stencil8;
+
+pub(crate) fn create_adapter(instance: &Instance, surface: &Surface<'static>, preference: &AdapterPreference) -> Result<Adapter, ThrustlerError> {
+    let adapter = instance
         .enumerate_adapters(Backends::all())
         .into_iter()
         .filter(|adapter| adapter.is_surface_supported(surface))
-        .next()
+        .min_by_key(|adapter| {
+            let info = adapter.get_info();
+            preference.rank(&info.name, to_adapter_kind(info.device_type))
+        })
         .ok_or(ThrustlerError::GraphicalBackendError)
-        .attach_printable("Can't create wgpu surface")
+        .attach_printable("Can't create wgpu surface")?;
+
+    let info = adapter.get_info();
+    println!("Wgpu uses the adapter {} ({:?}, {:?})", info.name, info.device_type, info.backend);
+    Ok(adapter)
+}
+
+fn to_adapter_kind(device_type: DeviceType) -> AdapterKind {
+    match device_type {
+        DeviceType::DiscreteGpu => AdapterKind::Discrete,
+        DeviceType::IntegratedGpu => AdapterKind::Integrated,
+        DeviceType::VirtualGpu => AdapterKind::Virtual,
+        DeviceType::Cpu => AdapterKind::Software,
+        DeviceType::Other => AdapterKind::Other,
+    }
 }
 
 pub(crate) fn pick_device_and_queue(adapter: &Adapter) -> Result<(Device, Queue), ThrustlerError> {
     adapter.request_device(
         &DeviceDescriptor {
-            required_features: Features::empty(),
+            //sample counts other than 1 and 4 depend on the adapter
+            required_features: adapter.features() & Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
             // WebGL doesn't support all of wgpu's features, so if
             // we're building for the web, we'll have to disable some.
             required_limits: if cfg!(target_arch = "wasm32") {
@@ -52,7 +75,7 @@ pub(crate) fn pick_device_and_queue(adapter: &Adapter) -> Result<(Device, Queue)
         .change_context(ThrustlerError::GraphicalBackendError)
 }
 
-pub(crate) fn create_surface_config(screen_size: Size, surface: &Surface<'static>, adapter: &Adapter) -> Result<SurfaceConfiguration, ThrustlerError> {
+pub(crate) fn create_surface_config(screen_size: Size, surface: &Surface<'static>, adapter: &Adapter, render_settings: &RenderSettings) -> Result<SurfaceConfiguration, ThrustlerError> {
     let surface_caps = surface.get_capabilities(&adapter);
 
     surface_caps.alpha_modes.iter().for_each(|e| {
@@ -65,47 +88,190 @@ pub(crate) fn create_surface_config(screen_size: Size, surface: &Surface<'static
         .ok_or(ThrustlerError::GraphicalBackendError)
         .attach_printable("Can't find appropriate format")?;
 
+    let present_mode = render_settings.present_mode
+        .choose(|present_mode| surface_caps.present_modes.contains(&to_wgpu_present_mode(present_mode)));
+    if present_mode != render_settings.present_mode {
+        println!("Present mode {:?} isn't supported, {present_mode:?} is used", render_settings.present_mode);
+    }
+
     Ok(SurfaceConfiguration {
         usage: TextureUsages::RENDER_ATTACHMENT,
         format: surface_format,
         width: screen_size.width,
         height: screen_size.height,
-        present_mode: PresentMode::Fifo,
+        present_mode: to_wgpu_present_mode(present_mode),
         alpha_mode: CompositeAlphaMode::Opaque,
         view_formats: vec![],
-        desired_maximum_frame_latency: 2,
+        desired_maximum_frame_latency: render_settings.frames_in_flight.max(1),
     })
 }
 
-pub(crate) fn create_render_pipeline(device: &Device, config: &SurfaceConfiguration) -> RenderPipeline {
+fn to_wgpu_present_mode(present_mode: ThrustlerPresentMode) -> PresentMode {
+    match present_mode {
+        ThrustlerPresentMode::Fifo => PresentMode::Fifo,
+        ThrustlerPresentMode::FifoRelaxed => PresentMode::FifoRelaxed,
+        ThrustlerPresentMode::Mailbox => PresentMode::Mailbox,
+        ThrustlerPresentMode::Immediate => PresentMode::Immediate,
+    }
+}
+
+/// The sample count of the settings or the highest one which the adapter supports for the surface format.
+pub(crate) fn pick_sample_count(adapter: &Adapter, config: &SurfaceConfiguration, render_settings: &RenderSettings) -> u32 {
+    let format_features = adapter.get_texture_format_features(config.format);
+    let stencil_features = adapter.get_texture_format_features(STENCIL_FORMAT);
+    let has_adapter_specific_counts = adapter.features().contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    let sample_count = render_settings.supported_sample_count(|sample_count| {
+        //4 samples are guaranteed by WebGPU
+        sample_count == 4 || (has_adapter_specific_counts
+            && format_features.flags.sample_count_supported(sample_count)
+            && stencil_features.flags.sample_count_supported(sample_count))
+    });
+    if sample_count != render_settings.sample_count {
+        println!("MSAA with {} samples isn't supported, {sample_count} samples are used", render_settings.sample_count);
+    }
+    sample_count
+}
+
+/// The texture which is drawn with MSAA and resolved into the surface texture, `None` without MSAA.
+fn create_multisampled_view(device: &Device, config: &SurfaceConfiguration, sample_count: u32) -> Option<TextureView> {
+    if sample_count == 1 {
+        return None;
+    }
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("Thrustler multisampled texture"),
+        size: Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: TextureDimension::D2,
+        format: config.format,
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&TextureViewDescriptor::default()))
+}
+
+/// The stencil buffer which masks game objects, it has the same size and sample count as the color target.
+fn create_stencil_view(device: &Device, config: &SurfaceConfiguration, sample_count: u32) -> TextureView {
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("Thrustler stencil texture"),
+        size: Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: TextureDimension::D2,
+        format: STENCIL_FORMAT,
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    texture.create_view(&TextureViewDescriptor::default())
+}
+
+/// A pipeline for every stencil mode, the stencil value is set for every game object.
+pub(crate) struct StencilPipelines {
+    none: RenderPipeline,
+    mask: RenderPipeline,
+    inside: RenderPipeline,
+    outside: RenderPipeline,
+}
+
+impl StencilPipelines {
+    fn get(&self, stencil: StencilMode) -> &RenderPipeline {
+        match stencil {
+            StencilMode::None => &self.none,
+            StencilMode::Mask(_) => &self.mask,
+            StencilMode::Inside(_) => &self.inside,
+            StencilMode::Outside(_) => &self.outside,
+        }
+    }
+}
+
+/// Pipelines of the built-in shader which draws game objects without their own shader.
+pub(crate) fn create_default_render_pipelines(device: &Device, config: &SurfaceConfiguration, sample_count: u32) -> StencilPipelines {
     let shader_module = device.create_shader_module(include_wgsl!(
             "../../../assets/shaders/wgsl/simple_shader.wgsl"
         ));
+    create_render_pipelines(device, &shader_module, config, sample_count)
+}
 
+/// Pipelines of a shader supplied at runtime, the errors which wgpu would panic with are returned instead.
+fn create_custom_render_pipelines(device: &Device, shader: &Shader, config: &SurfaceConfiguration, sample_count: u32) -> Result<StencilPipelines, ThrustlerError> {
+    device.push_error_scope(ErrorFilter::Validation);
+    let shader_module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Thrustler custom shader"),
+        source: ShaderSource::Wgsl(shader.wgsl().into()),
+    });
+    let pipelines = create_render_pipelines(device, &shader_module, config, sample_count);
+    match device.pop_error_scope().block_on() {
+        Some(error) => Err(Report::new(ThrustlerError::ShaderError))
+            .attach_printable(error.to_string()),
+        None => Ok(pipelines),
+    }
+}
+
+fn create_render_pipelines(device: &Device, shader_module: &ShaderModule, config: &SurfaceConfiguration, sample_count: u32) -> StencilPipelines {
     let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: Some("Thruster pipeline Layout"),
         bind_group_layouts: &[],
         push_constant_ranges: &[],
     });
 
+    let pipeline_for = |stencil| create_render_pipeline(device, shader_module, &pipeline_layout, config, sample_count, stencil);
+    StencilPipelines {
+        none: pipeline_for(StencilMode::None),
+        mask: pipeline_for(StencilMode::Mask(0)),
+        inside: pipeline_for(StencilMode::Inside(0)),
+        outside: pipeline_for(StencilMode::Outside(0)),
+    }
+}
+
+fn create_render_pipeline(
+    device: &Device,
+    shader_module: &ShaderModule,
+    pipeline_layout: &PipelineLayout,
+    config: &SurfaceConfiguration,
+    sample_count: u32,
+    stencil: StencilMode,
+) -> RenderPipeline {
+    let (compare, pass_op) = match stencil {
+        StencilMode::None => (CompareFunction::Always, StencilOperation::Keep),
+        StencilMode::Mask(_) => (CompareFunction::Always, StencilOperation::Replace),
+        StencilMode::Inside(_) => (CompareFunction::Equal, StencilOperation::Keep),
+        StencilMode::Outside(_) => (CompareFunction::NotEqual, StencilOperation::Keep),
+    };
+    let stencil_face = StencilFaceState {
+        compare,
+        fail_op: StencilOperation::Keep,
+        depth_fail_op: StencilOperation::Keep,
+        pass_op,
+    };
+    let is_mask = matches!(stencil, StencilMode::Mask(_));
+
     device.create_render_pipeline(&RenderPipelineDescriptor {
         label: None,
-        layout: Some(&pipeline_layout),
+        layout: Some(pipeline_layout),
         vertex: VertexState {
-            module: &shader_module,
+            module: shader_module,
             entry_point: "vs_main",
             buffers: &[create_vertex_layout()],
             compilation_options: Default::default(),
         },
         fragment: Some(FragmentState {
-            module: &shader_module,
+            module: shader_module,
             entry_point: "fs_main",
             compilation_options: Default::default(),
             targets: &[
                 Some(ColorTargetState {
                     format: config.format,
                     blend: Some(BlendState::REPLACE),
-                    write_mask: ColorWrites::ALL,
+                    //masks only mark the stencil buffer
+                    write_mask: if is_mask { ColorWrites::empty() } else { ColorWrites::ALL },
                 })
             ],
         }),
@@ -118,9 +284,20 @@ pub(crate) fn create_render_pipeline(device: &Device, config: &SurfaceConfigurat
             unclipped_depth: false,
             conservative: false,
         },
-        depth_stencil: None,
+        depth_stencil: Some(DepthStencilState {
+            format: STENCIL_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: CompareFunction::Always,
+            stencil: StencilState {
+                front: stencil_face,
+                back: stencil_face,
+                read_mask: 0xff,
+                write_mask: if is_mask { 0xff } else { 0 },
+            },
+            bias: DepthBiasState::default(),
+        }),
         multisample: MultisampleState {
-            count: 1,
+            count: sample_count,
             mask: !0,
             alpha_to_coverage_enabled: false,
         },
@@ -142,37 +319,128 @@ fn create_vertex_layout() -> VertexBufferLayout<'static> {
     }
 }
 
+//buffers are cached per scene, they are keyed by the index of the scene in the window and the id of the object
+type VerticesBufferCache = HashMap<(usize, Uuid), (Rc<Buffer>, bool)>;
+
 pub struct CommandBufferExecutor {
-    vertices_buffer_cache: RefCell<HashMap<Uuid, (Rc<Buffer>, bool)>>,
+    vertices_buffer_cache: RefCell<VerticesBufferCache>,
     surface: Surface<'static>,
+    surface_config: SurfaceConfiguration,
     device: Device,
     queue: Queue,
-    render_pipeline: RenderPipeline,
+    render_pipelines: StencilPipelines,
+    //pipelines of shaders supplied at runtime, they are created when an object with the shader is drawn first
+    custom_pipelines: HashMap<ShaderId, StencilPipelines>,
+    //shaders which can't be compiled, their objects are drawn with the built-in pipelines until the shader is reloaded
+    failed_shaders: HashSet<ShaderId>,
+    sample_count: u32,
+    multisampled_view: Option<TextureView>,
+    stencil_view: TextureView,
+    render_stats: Cell<RenderStats>,
 }
 
 impl CommandBufferExecutor {
-    pub fn new(surface: Surface<'static>, device: Device, queue: Queue, render_pipeline: RenderPipeline) -> Self {
+    pub fn new(surface: Surface<'static>, surface_config: SurfaceConfiguration, device: Device, queue: Queue, render_pipelines: StencilPipelines, sample_count: u32) -> Self {
+        let multisampled_view = create_multisampled_view(&device, &surface_config, sample_count);
+        let stencil_view = create_stencil_view(&device, &surface_config, sample_count);
         Self {
             vertices_buffer_cache: RefCell::new(HashMap::new()),
             surface,
+            surface_config,
             device,
             queue,
-            render_pipeline,
+            render_pipelines,
+            custom_pipelines: HashMap::new(),
+            failed_shaders: HashSet::new(),
+            sample_count,
+            multisampled_view,
+            stencil_view,
+            render_stats: Cell::new(RenderStats::default()),
         }
     }
 
-    pub fn execute_buffer(&mut self, game_objects: &Vec<GameObject>) -> Result<(), ThrustlerError> {
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats.get()
+    }
+
+    fn update_render_stats(&self, update: impl FnOnce(&mut RenderStats)) {
+        let mut render_stats = self.render_stats.get();
+        update(&mut render_stats);
+        self.render_stats.set(render_stats);
+    }
+
+    /// Reconfigures the surface with the new size.
+    pub fn resize(&mut self, size: Size) {
+        self.surface_config.width = size.width;
+        self.surface_config.height = size.height;
+        self.surface.configure(&self.device, &self.surface_config);
+        self.multisampled_view = create_multisampled_view(&self.device, &self.surface_config, self.sample_count);
+        self.stencil_view = create_stencil_view(&self.device, &self.surface_config, self.sample_count);
+    }
+
+    /// Draws objects of all scenes into the next surface texture, scenes are drawn over each other in their order.
+    pub fn execute_buffer(&mut self, scene_objects: &[&Vec<GameObject>]) -> Result<(), ThrustlerError> {
+        self.prepare_pipelines(scene_objects);
         let (current_texture, texture_view) = self.acquire_next_surface()?;
-        let command_buffer = self.fill_render_pass(texture_view, game_objects);
+        let command_buffer = self.fill_render_pass(texture_view, scene_objects);
         self.queue.submit(std::iter::once(command_buffer));
         current_texture.present();
         Ok(())
     }
 
+    /// Creates pipelines of shaders which haven't been drawn yet, the render pass can only borrow them.
+    /// A shader which fails is reported once and its objects are drawn with the built-in pipelines.
+    fn prepare_pipelines(&mut self, scene_objects: &[&Vec<GameObject>]) {
+        let game_objects = scene_objects.iter().flat_map(|game_objects| game_objects.iter());
+        for shader in game_objects.filter_map(|game_object| game_object.shader.as_ref()) {
+            if self.custom_pipelines.contains_key(&shader.id()) || self.failed_shaders.contains(&shader.id()) {
+                continue;
+            }
+            match create_custom_render_pipelines(&self.device, shader, &self.surface_config, self.sample_count) {
+                Ok(pipelines) => {
+                    self.custom_pipelines.insert(shader.id(), pipelines);
+                }
+                Err(report) => {
+                    println!("Shader {:?} can't be used, the built-in one is used instead: {report:?}", shader.id());
+                    self.failed_shaders.insert(shader.id());
+                }
+            }
+        }
+    }
+
+    /// Replaces pipelines of the shader, the previous ones are kept if the new source fails.
+    pub fn reload_shader(&mut self, shader: &Shader) -> Result<(), ThrustlerError> {
+        let pipelines = create_custom_render_pipelines(&self.device, shader, &self.surface_config, self.sample_count)?;
+        if shader.id() == ShaderId::BUILT_IN {
+            self.render_pipelines = pipelines;
+        } else {
+            self.failed_shaders.remove(&shader.id());
+            self.custom_pipelines.insert(shader.id(), pipelines);
+        }
+        Ok(())
+    }
+
+    fn pipeline(&self, game_object: &GameObject) -> &RenderPipeline {
+        game_object.shader.as_ref()
+            .and_then(|shader| self.custom_pipelines.get(&shader.id()))
+            .unwrap_or(&self.render_pipelines)
+            .get(game_object.stencil)
+    }
+
     fn acquire_next_surface(&self) -> Result<(SurfaceTexture, TextureView), ThrustlerError> {
         let current_texture = self.surface.get_current_texture()
-            .attach_printable("Can't get current texture")
-            .change_context(ThrustlerError::GraphicalBackendError)?;
+            .map_err(|err| {
+                let context = match err {
+                    SurfaceError::Lost | SurfaceError::Outdated => {
+                        self.surface.configure(&self.device, &self.surface_config);
+                        ThrustlerError::RecoverableBackendError
+                    }
+                    SurfaceError::Timeout => ThrustlerError::RecoverableBackendError,
+                    SurfaceError::OutOfMemory => ThrustlerError::GraphicalBackendError,
+                };
+                Report::new(err).change_context(context)
+            })
+            .attach_printable("Can't get current texture")?;
 
         let texture_view = current_texture.texture.create_view(&TextureViewDescriptor::default());
         Ok((current_texture, texture_view))
@@ -192,17 +460,22 @@ impl CommandBufferExecutor {
         )
     }
 
-    fn fill_render_pass(&mut self, texture_view: TextureView, game_objects: &Vec<GameObject>) -> CommandBuffer {
+    fn fill_render_pass(&mut self, texture_view: TextureView, scene_objects: &[&Vec<GameObject>]) -> CommandBuffer {
         let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("Thrustler encoder"),
         });
+        //with MSAA the scene is drawn into the multisampled texture and resolved into the surface one
+        let (view, resolve_target, store) = match &self.multisampled_view {
+            Some(multisampled_view) => (multisampled_view, Some(&texture_view), StoreOp::Discard),
+            None => (&texture_view, None, StoreOp::Store),
+        };
         {
             let mut render_pass = encoder.begin_render_pass(
                 &RenderPassDescriptor {
                     label: Some("Thrustler encoder"),
                     color_attachments: &[Some(RenderPassColorAttachment {
-                        view: &texture_view,
-                        resolve_target: None,
+                        view,
+                        resolve_target,
                         ops: Operations {
                             load: LoadOp::Clear(
                                 Color {
@@ -212,42 +485,56 @@ impl CommandBufferExecutor {
                                     a: 1.0,
                                 }
                             ),
-                            store: StoreOp::Store,
+                            store,
                         },
                     })],
-                    depth_stencil_attachment: None,
+                    depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                        view: &self.stencil_view,
+                        depth_ops: None,
+                        stencil_ops: Some(Operations {
+                            load: LoadOp::Clear(0),
+                            store: StoreOp::Discard,
+                        }),
+                    }),
                     occlusion_query_set: None,
                     timestamp_writes: None,
                 }
             );
 
-            {
-                render_pass.set_pipeline(&self.render_pipeline);
-            }
-
+            self.render_stats.set(RenderStats::default());
             self.mark_buffers_as_unused();
-            for game_object in game_objects {
+            let game_objects = scene_objects.iter()
+                .enumerate()
+                .flat_map(|(scene_index, game_objects)| game_objects.iter().map(move |game_object| (scene_index, game_object)));
+            for (scene_index, game_object) in game_objects {
                 let vert = {
-                    let vertex_buffer = self.get_buffer_slice_for_game_object(game_object);
+                    let vertex_buffer = self.get_buffer_slice_for_game_object(scene_index, game_object);
                     unsafe { Rc::as_ptr(&vertex_buffer).as_ref().unwrap() }
                 };
+                render_pass.set_pipeline(self.pipeline(game_object));
+                render_pass.set_stencil_reference(game_object.stencil.reference());
                 render_pass.set_vertex_buffer(0, vert.slice(..));
-                render_pass.draw(0..3, 0..1);
+                render_pass.draw(0..game_object.vertices.len() as u32, 0..1);
+                self.update_render_stats(|stats| stats.draw_calls += 1);
             }
             self.delete_all_unused_buffers();
+            let cached_buffers = self.vertices_buffer_cache.borrow().len();
+            self.update_render_stats(|stats| stats.cached_buffers = cached_buffers);
         };
         encoder.finish()
     }
 
-    fn get_buffer_slice_for_game_object(&self, game_object: &GameObject) -> Rc<Buffer> {
+    fn get_buffer_slice_for_game_object(&self, scene_index: usize, game_object: &GameObject) -> Rc<Buffer> {
         let mut borrowed_cache = self.vertices_buffer_cache.borrow_mut();
+        let key = (scene_index, game_object.id);
 
-        if let Some(data) = borrowed_cache.get_mut(&game_object.id) {
+        if let Some(data) = borrowed_cache.get_mut(&key) {
             data.1 = true;
             data.0.clone()
         } else {
             let rc_buffer = Rc::new(self.create_vertices_buffer(game_object));
-            borrowed_cache.insert(game_object.id, (rc_buffer.clone(), true));
+            self.update_render_stats(|stats| stats.uploaded_bytes += rc_buffer.size());
+            borrowed_cache.insert(key, (rc_buffer.clone(), true));
             rc_buffer
         }
     }
@@ -259,7 +546,7 @@ impl CommandBufferExecutor {
     }
 
     fn delete_all_unused_buffers(&self) {
-        let dead_buffer_uuids: Vec<_> = self.vertices_buffer_cache.borrow().iter().filter_map(|bucket| {
+        let dead_buffer_keys: Vec<_> = self.vertices_buffer_cache.borrow().iter().filter_map(|bucket| {
             if !bucket.1.1 {
                 Some(*bucket.0)
             } else {
@@ -267,8 +554,8 @@ impl CommandBufferExecutor {
             }
         }).collect();
 
-        for dead_buffer_uuid in dead_buffer_uuids {
-            self.vertices_buffer_cache.borrow_mut().remove(&dead_buffer_uuid);
+        for dead_buffer_key in dead_buffer_keys {
+            self.vertices_buffer_cache.borrow_mut().remove(&dead_buffer_key);
         }
     }
 }
@@ -0,0 +1,40 @@
+use winit::event_loop::ActiveEventLoop;
+use winit::window::{Cursor as WinitCursor, CursorGrabMode, CursorIcon, CustomCursor, Window};
+
+use core::window::{Cursor, CursorGrab};
+
+pub(crate) fn set_cursor_grab(window: &Window, cursor_grab: CursorGrab) {
+    //platforms support either confining or locking, so the other mode is tried as well
+    let modes = match cursor_grab {
+        CursorGrab::None => vec![CursorGrabMode::None],
+        CursorGrab::Confined => vec![CursorGrabMode::Confined, CursorGrabMode::Locked],
+        CursorGrab::Locked => vec![CursorGrabMode::Locked, CursorGrabMode::Confined],
+    };
+
+    let mut last_error = None;
+    for mode in modes {
+        match window.set_cursor_grab(mode) {
+            Ok(()) => return,
+            Err(error) => last_error = Some(error),
+        }
+    }
+    if let Some(error) = last_error {
+        println!("Can't grab the cursor with {cursor_grab:?}: {error}");
+    }
+}
+
+pub(crate) fn to_winit_cursor(event_loop: &ActiveEventLoop, cursor: Cursor) -> Option<WinitCursor> {
+    match cursor {
+        Cursor::Default => Some(WinitCursor::Icon(CursorIcon::Default)),
+        Cursor::Custom(image) => {
+            let [hotspot_x, hotspot_y] = image.hotspot;
+            match CustomCursor::from_rgba(image.rgba, image.width, image.height, hotspot_x, hotspot_y) {
+                Ok(source) => Some(WinitCursor::Custom(event_loop.create_custom_cursor(source))),
+                Err(error) => {
+                    println!("Invalid cursor image: {error}");
+                    None
+                }
+            }
+        }
+    }
+}
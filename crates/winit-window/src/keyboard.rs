@@ -0,0 +1,177 @@
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{Key as WinitKey, KeyCode as WinitKeyCode, ModifiersState, NamedKey as WinitNamedKey, PhysicalKey};
+
+use core::input::keyboard::{Key, KeyboardEvent, KeyCode, KeyState, Modifiers, NamedKey};
+
+pub(crate) fn to_keyboard_event(event: &KeyEvent, modifiers: ModifiersState) -> KeyboardEvent {
+    let state = match event.state {
+        ElementState::Pressed if event.repeat => KeyState::Repeated,
+        ElementState::Pressed => KeyState::Pressed,
+        ElementState::Released => KeyState::Released,
+    };
+
+    KeyboardEvent {
+        physical_key: to_key_code(event.physical_key),
+        logical_key: to_key(&event.logical_key),
+        state,
+        modifiers: to_modifiers(modifiers),
+    }
+}
+
+pub(crate) fn to_modifiers(modifiers: ModifiersState) -> Modifiers {
+    Modifiers {
+        shift: modifiers.shift_key(),
+        control: modifiers.control_key(),
+        alt: modifiers.alt_key(),
+        super_key: modifiers.super_key(),
+    }
+}
+
+fn to_key(key: &WinitKey) -> Key {
+    match key {
+        WinitKey::Character(character) => Key::Character(character.to_string()),
+        WinitKey::Named(WinitNamedKey::Alt) => Key::Named(NamedKey::Alt),
+        WinitKey::Named(WinitNamedKey::CapsLock) => Key::Named(NamedKey::CapsLock),
+        WinitKey::Named(WinitNamedKey::Control) => Key::Named(NamedKey::Control),
+        WinitKey::Named(WinitNamedKey::Shift) => Key::Named(NamedKey::Shift),
+        WinitKey::Named(WinitNamedKey::Super) => Key::Named(NamedKey::Super),
+        WinitKey::Named(WinitNamedKey::Enter) => Key::Named(NamedKey::Enter),
+        WinitKey::Named(WinitNamedKey::Tab) => Key::Named(NamedKey::Tab),
+        WinitKey::Named(WinitNamedKey::Space) => Key::Named(NamedKey::Space),
+        WinitKey::Named(WinitNamedKey::ArrowDown) => Key::Named(NamedKey::ArrowDown),
+        WinitKey::Named(WinitNamedKey::ArrowLeft) => Key::Named(NamedKey::ArrowLeft),
+        WinitKey::Named(WinitNamedKey::ArrowRight) => Key::Named(NamedKey::ArrowRight),
+        WinitKey::Named(WinitNamedKey::ArrowUp) => Key::Named(NamedKey::ArrowUp),
+        WinitKey::Named(WinitNamedKey::End) => Key::Named(NamedKey::End),
+        WinitKey::Named(WinitNamedKey::Home) => Key::Named(NamedKey::Home),
+        WinitKey::Named(WinitNamedKey::PageDown) => Key::Named(NamedKey::PageDown),
+        WinitKey::Named(WinitNamedKey::PageUp) => Key::Named(NamedKey::PageUp),
+        WinitKey::Named(WinitNamedKey::Backspace) => Key::Named(NamedKey::Backspace),
+        WinitKey::Named(WinitNamedKey::Delete) => Key::Named(NamedKey::Delete),
+        WinitKey::Named(WinitNamedKey::Insert) => Key::Named(NamedKey::Insert),
+        WinitKey::Named(WinitNamedKey::Escape) => Key::Named(NamedKey::Escape),
+        WinitKey::Named(WinitNamedKey::F1) => Key::Named(NamedKey::F1),
+        WinitKey::Named(WinitNamedKey::F2) => Key::Named(NamedKey::F2),
+        WinitKey::Named(WinitNamedKey::F3) => Key::Named(NamedKey::F3),
+        WinitKey::Named(WinitNamedKey::F4) => Key::Named(NamedKey::F4),
+        WinitKey::Named(WinitNamedKey::F5) => Key::Named(NamedKey::F5),
+        WinitKey::Named(WinitNamedKey::F6) => Key::Named(NamedKey::F6),
+        WinitKey::Named(WinitNamedKey::F7) => Key::Named(NamedKey::F7),
+        WinitKey::Named(WinitNamedKey::F8) => Key::Named(NamedKey::F8),
+        WinitKey::Named(WinitNamedKey::F9) => Key::Named(NamedKey::F9),
+        WinitKey::Named(WinitNamedKey::F10) => Key::Named(NamedKey::F10),
+        WinitKey::Named(WinitNamedKey::F11) => Key::Named(NamedKey::F11),
+        WinitKey::Named(WinitNamedKey::F12) => Key::Named(NamedKey::F12),
+        _ => Key::Unidentified,
+    }
+}
+
+fn to_key_code(key: PhysicalKey) -> KeyCode {
+    match key {
+        PhysicalKey::Code(WinitKeyCode::KeyA) => KeyCode::KeyA,
+        PhysicalKey::Code(WinitKeyCode::KeyB) => KeyCode::KeyB,
+        PhysicalKey::Code(WinitKeyCode::KeyC) => KeyCode::KeyC,
+        PhysicalKey::Code(WinitKeyCode::KeyD) => KeyCode::KeyD,
+        PhysicalKey::Code(WinitKeyCode::KeyE) => KeyCode::KeyE,
+        PhysicalKey::Code(WinitKeyCode::KeyF) => KeyCode::KeyF,
+        PhysicalKey::Code(WinitKeyCode::KeyG) => KeyCode::KeyG,
+        PhysicalKey::Code(WinitKeyCode::KeyH) => KeyCode::KeyH,
+        PhysicalKey::Code(WinitKeyCode::KeyI) => KeyCode::KeyI,
+        PhysicalKey::Code(WinitKeyCode::KeyJ) => KeyCode::KeyJ,
+        PhysicalKey::Code(WinitKeyCode::KeyK) => KeyCode::KeyK,
+        PhysicalKey::Code(WinitKeyCode::KeyL) => KeyCode::KeyL,
+        PhysicalKey::Code(WinitKeyCode::KeyM) => KeyCode::KeyM,
+        PhysicalKey::Code(WinitKeyCode::KeyN) => KeyCode::KeyN,
+        PhysicalKey::Code(WinitKeyCode::KeyO) => KeyCode::KeyO,
+        PhysicalKey::Code(WinitKeyCode::KeyP) => KeyCode::KeyP,
+        PhysicalKey::Code(WinitKeyCode::KeyQ) => KeyCode::KeyQ,
+        PhysicalKey::Code(WinitKeyCode::KeyR) => KeyCode::KeyR,
+        PhysicalKey::Code(WinitKeyCode::KeyS) => KeyCode::KeyS,
+        PhysicalKey::Code(WinitKeyCode::KeyT) => KeyCode::KeyT,
+        PhysicalKey::Code(WinitKeyCode::KeyU) => KeyCode::KeyU,
+        PhysicalKey::Code(WinitKeyCode::KeyV) => KeyCode::KeyV,
+        PhysicalKey::Code(WinitKeyCode::KeyW) => KeyCode::KeyW,
+        PhysicalKey::Code(WinitKeyCode::KeyX) => KeyCode::KeyX,
+        PhysicalKey::Code(WinitKeyCode::KeyY) => KeyCode::KeyY,
+        PhysicalKey::Code(WinitKeyCode::KeyZ) => KeyCode::KeyZ,
+        PhysicalKey::Code(WinitKeyCode::Digit0) => KeyCode::Digit0,
+        PhysicalKey::Code(WinitKeyCode::Digit1) => KeyCode::Digit1,
+        PhysicalKey::Code(WinitKeyCode::Digit2) => KeyCode::Digit2,
+        PhysicalKey::Code(WinitKeyCode::Digit3) => KeyCode::Digit3,
+        PhysicalKey::Code(WinitKeyCode::Digit4) => KeyCode::Digit4,
+        PhysicalKey::Code(WinitKeyCode::Digit5) => KeyCode::Digit5,
+        PhysicalKey::Code(WinitKeyCode::Digit6) => KeyCode::Digit6,
+        PhysicalKey::Code(WinitKeyCode::Digit7) => KeyCode::Digit7,
+        PhysicalKey::Code(WinitKeyCode::Digit8) => KeyCode::Digit8,
+        PhysicalKey::Code(WinitKeyCode::Digit9) => KeyCode::Digit9,
+        PhysicalKey::Code(WinitKeyCode::Backquote) => KeyCode::Backquote,
+        PhysicalKey::Code(WinitKeyCode::Backslash) => KeyCode::Backslash,
+        PhysicalKey::Code(WinitKeyCode::BracketLeft) => KeyCode::BracketLeft,
+        PhysicalKey::Code(WinitKeyCode::BracketRight) => KeyCode::BracketRight,
+        PhysicalKey::Code(WinitKeyCode::Comma) => KeyCode::Comma,
+        PhysicalKey::Code(WinitKeyCode::Equal) => KeyCode::Equal,
+        PhysicalKey::Code(WinitKeyCode::Minus) => KeyCode::Minus,
+        PhysicalKey::Code(WinitKeyCode::Period) => KeyCode::Period,
+        PhysicalKey::Code(WinitKeyCode::Quote) => KeyCode::Quote,
+        PhysicalKey::Code(WinitKeyCode::Semicolon) => KeyCode::Semicolon,
+        PhysicalKey::Code(WinitKeyCode::Slash) => KeyCode::Slash,
+        PhysicalKey::Code(WinitKeyCode::AltLeft) => KeyCode::AltLeft,
+        PhysicalKey::Code(WinitKeyCode::AltRight) => KeyCode::AltRight,
+        PhysicalKey::Code(WinitKeyCode::Backspace) => KeyCode::Backspace,
+        PhysicalKey::Code(WinitKeyCode::CapsLock) => KeyCode::CapsLock,
+        PhysicalKey::Code(WinitKeyCode::ContextMenu) => KeyCode::ContextMenu,
+        PhysicalKey::Code(WinitKeyCode::ControlLeft) => KeyCode::ControlLeft,
+        PhysicalKey::Code(WinitKeyCode::ControlRight) => KeyCode::ControlRight,
+        PhysicalKey::Code(WinitKeyCode::Enter) => KeyCode::Enter,
+        PhysicalKey::Code(WinitKeyCode::ShiftLeft) => KeyCode::ShiftLeft,
+        PhysicalKey::Code(WinitKeyCode::ShiftRight) => KeyCode::ShiftRight,
+        PhysicalKey::Code(WinitKeyCode::SuperLeft) => KeyCode::SuperLeft,
+        PhysicalKey::Code(WinitKeyCode::SuperRight) => KeyCode::SuperRight,
+        PhysicalKey::Code(WinitKeyCode::Space) => KeyCode::Space,
+        PhysicalKey::Code(WinitKeyCode::Tab) => KeyCode::Tab,
+        PhysicalKey::Code(WinitKeyCode::Delete) => KeyCode::Delete,
+        PhysicalKey::Code(WinitKeyCode::End) => KeyCode::End,
+        PhysicalKey::Code(WinitKeyCode::Home) => KeyCode::Home,
+        PhysicalKey::Code(WinitKeyCode::Insert) => KeyCode::Insert,
+        PhysicalKey::Code(WinitKeyCode::PageDown) => KeyCode::PageDown,
+        PhysicalKey::Code(WinitKeyCode::PageUp) => KeyCode::PageUp,
+        PhysicalKey::Code(WinitKeyCode::ArrowDown) => KeyCode::ArrowDown,
+        PhysicalKey::Code(WinitKeyCode::ArrowLeft) => KeyCode::ArrowLeft,
+        PhysicalKey::Code(WinitKeyCode::ArrowRight) => KeyCode::ArrowRight,
+        PhysicalKey::Code(WinitKeyCode::ArrowUp) => KeyCode::ArrowUp,
+        PhysicalKey::Code(WinitKeyCode::NumLock) => KeyCode::NumLock,
+        PhysicalKey::Code(WinitKeyCode::Numpad0) => KeyCode::Numpad0,
+        PhysicalKey::Code(WinitKeyCode::Numpad1) => KeyCode::Numpad1,
+        PhysicalKey::Code(WinitKeyCode::Numpad2) => KeyCode::Numpad2,
+        PhysicalKey::Code(WinitKeyCode::Numpad3) => KeyCode::Numpad3,
+        PhysicalKey::Code(WinitKeyCode::Numpad4) => KeyCode::Numpad4,
+        PhysicalKey::Code(WinitKeyCode::Numpad5) => KeyCode::Numpad5,
+        PhysicalKey::Code(WinitKeyCode::Numpad6) => KeyCode::Numpad6,
+        PhysicalKey::Code(WinitKeyCode::Numpad7) => KeyCode::Numpad7,
+        PhysicalKey::Code(WinitKeyCode::Numpad8) => KeyCode::Numpad8,
+        PhysicalKey::Code(WinitKeyCode::Numpad9) => KeyCode::Numpad9,
+        PhysicalKey::Code(WinitKeyCode::NumpadAdd) => KeyCode::NumpadAdd,
+        PhysicalKey::Code(WinitKeyCode::NumpadDecimal) => KeyCode::NumpadDecimal,
+        PhysicalKey::Code(WinitKeyCode::NumpadDivide) => KeyCode::NumpadDivide,
+        PhysicalKey::Code(WinitKeyCode::NumpadEnter) => KeyCode::NumpadEnter,
+        PhysicalKey::Code(WinitKeyCode::NumpadMultiply) => KeyCode::NumpadMultiply,
+        PhysicalKey::Code(WinitKeyCode::NumpadSubtract) => KeyCode::NumpadSubtract,
+        PhysicalKey::Code(WinitKeyCode::Escape) => KeyCode::Escape,
+        PhysicalKey::Code(WinitKeyCode::PrintScreen) => KeyCode::PrintScreen,
+        PhysicalKey::Code(WinitKeyCode::ScrollLock) => KeyCode::ScrollLock,
+        PhysicalKey::Code(WinitKeyCode::Pause) => KeyCode::Pause,
+        PhysicalKey::Code(WinitKeyCode::F1) => KeyCode::F1,
+        PhysicalKey::Code(WinitKeyCode::F2) => KeyCode::F2,
+        PhysicalKey::Code(WinitKeyCode::F3) => KeyCode::F3,
+        PhysicalKey::Code(WinitKeyCode::F4) => KeyCode::F4,
+        PhysicalKey::Code(WinitKeyCode::F5) => KeyCode::F5,
+        PhysicalKey::Code(WinitKeyCode::F6) => KeyCode::F6,
+        PhysicalKey::Code(WinitKeyCode::F7) => KeyCode::F7,
+        PhysicalKey::Code(WinitKeyCode::F8) => KeyCode::F8,
+        PhysicalKey::Code(WinitKeyCode::F9) => KeyCode::F9,
+        PhysicalKey::Code(WinitKeyCode::F10) => KeyCode::F10,
+        PhysicalKey::Code(WinitKeyCode::F11) => KeyCode::F11,
+        PhysicalKey::Code(WinitKeyCode::F12) => KeyCode::F12,
+        _ => KeyCode::Unidentified,
+    }
+}
@@ -1,59 +1,87 @@
-use std::any::Any;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ops::DerefMut;
 use std::sync::Arc;
 
-use error_stack::Result;
+use error_stack::{Report, Result};
 use error_stack::ResultExt;
 use winit::application::ApplicationHandler;
-use winit::dpi::PhysicalSize;
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::event::{DeviceEvent, DeviceId};
 use winit::event_loop::ActiveEventLoop;
-use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::keyboard::ModifiersState;
+use winit::monitor::{MonitorHandle, VideoModeHandle};
+use winit::window::{Fullscreen as WinitFullscreen, Icon, Window, WindowAttributes, WindowId as WinitWindowId};
 
-use core::{Size, ThrustlerWindow, WindowEvent};
+use core::{EventDispatcher, Size, ThrustlerWindow, WindowEvent};
 use core::error::ThrustlerError;
+use core::input::InputEvent;
+use core::input::file_drop::FileDropEvent;
+use core::input::mouse::MouseEvent;
+use core::input::text::TextEvent;
+use core::window::{Fullscreen, OutputWindow, WindowCommand, WindowControl, WindowDescriptor, WindowId};
 use error::ThrustlerWindowError;
 
+mod cursor;
 mod error;
+mod keyboard;
+mod mouse;
+mod text;
+mod touch;
+
+/// Sets up the graphical backend of a window for its output window, it's called again when the application is resumed.
+pub type WindowSupplier = Box<dyn Fn(WindowId, Arc<dyn OutputWindow>) -> Result<(), ThrustlerError>>;
 
 pub struct WinitWindow {
     window_state: RefCell<WindowState>,
-    size: Size,
 }
 
 impl WinitWindow {
+    /// Opens a window for every descriptor, the window supplier is called for each of them before `OnStart`.
     pub fn new(
-        size: Size,
-        window_supplier: Box<dyn Fn(Arc<dyn OutputWindow>) -> Result<(), ThrustlerError>>,
+        windows: Vec<WindowDescriptor>,
+        window_supplier: WindowSupplier,
     ) -> Result<WinitWindow, ThrustlerWindowError> {
         let event_loop = winit::event_loop::EventLoop::new()
             .attach_printable("Can't create event loop")
             .change_context(ThrustlerWindowError::WindowLoopError)?;
 
-        let window_attrs = WindowAttributes::default()
-            .with_title("Thrustler")
-            .with_inner_size(PhysicalSize::new(size.width, size.height));
+        let pending_windows = windows.into_iter()
+            .map(PendingWindow::new)
+            .collect::<Result<Vec<_>, ThrustlerWindowError>>()?;
 
         Ok(Self {
             window_state: RefCell::new(WindowState {
-                window: None,
-                window_attrs: Some(window_attrs),
+                windows: HashMap::new(),
+                pending_windows,
+                window_control: None,
                 event_loop: Some(event_loop),
                 event_dispatcher: None,
+                dispatcher_error: None,
+                modifiers: ModifiersState::empty(),
+                focused_window: None,
+                is_composing: false,
+                cursor_positions: HashMap::new(),
+                is_started: false,
+                is_suspended: false,
                 window_supplier,
             }),
-            size,
         })
     }
 
-    pub fn run(&self, event_dispatcher: Box<dyn FnMut(WindowEvent)>) -> Result<(), ThrustlerWindowError> {
+    pub fn run(
+        &self,
+        event_dispatcher: EventDispatcher,
+        window_control: WindowControl,
+    ) -> Result<(), ThrustlerWindowError> {
         let event_loop = {
             self.window_state.borrow_mut().event_loop.take().ok_or(ThrustlerWindowError::WindowLoopError)?
         };
 
         {
-            self.window_state.borrow_mut().event_dispatcher.replace(event_dispatcher);
+            let mut window_state = self.window_state.borrow_mut();
+            window_state.event_dispatcher.replace(event_dispatcher);
+            window_state.window_control.replace(window_control);
         }
         event_loop.run_app(self.window_state.borrow_mut().deref_mut())
             .attach_printable("An event loop error has happened")
@@ -62,60 +90,342 @@ impl WinitWindow {
 }
 
 impl ThrustlerWindow for WinitWindow {
-    fn start(&self, dispatcher: Box<dyn FnMut(WindowEvent) -> ()>) -> Result<(), ThrustlerError> {
-        self.run(dispatcher).change_context(ThrustlerError::WindowError)
+    fn start(
+        &self,
+        dispatcher: EventDispatcher,
+        window_control: WindowControl,
+    ) -> Result<(), ThrustlerError> {
+        self.run(dispatcher, window_control).change_context(ThrustlerError::WindowError)?;
+
+        match self.window_state.borrow_mut().dispatcher_error.take() {
+            Some(report) => Err(report),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A window which is opened when the event loop is resumed.
+struct PendingWindow {
+    id: WindowId,
+    window_attrs: WindowAttributes,
+    //exclusive fullscreen needs a monitor, so it's resolved when the window is created
+    fullscreen: Option<Fullscreen>,
+}
+
+impl PendingWindow {
+    fn new(descriptor: WindowDescriptor) -> Result<PendingWindow, ThrustlerWindowError> {
+        let WindowDescriptor { id, size, settings } = descriptor;
+        let mut window_attrs = WindowAttributes::default()
+            .with_title(settings.title)
+            .with_inner_size(LogicalSize::new(size.width, size.height))
+            .with_resizable(settings.resizable)
+            .with_decorations(settings.decorations);
+        if let Some(icon) = settings.icon {
+            let icon = Icon::from_rgba(icon.rgba, icon.width, icon.height)
+                .attach_printable("Invalid window icon")
+                .change_context(ThrustlerWindowError::UnableToCreateWindow)?;
+            window_attrs = window_attrs.with_window_icon(Some(icon));
+        }
+        if let Some(min_size) = settings.min_size {
+            window_attrs = window_attrs.with_min_inner_size(LogicalSize::new(min_size.width, min_size.height));
+        }
+        if let Some(max_size) = settings.max_size {
+            window_attrs = window_attrs.with_max_inner_size(LogicalSize::new(max_size.width, max_size.height));
+        }
+        if let Some([x, y]) = settings.position {
+            window_attrs = window_attrs.with_position(PhysicalPosition::new(x, y));
+        }
+
+        Ok(Self {
+            id,
+            window_attrs,
+            fullscreen: settings.fullscreen,
+        })
     }
 }
 
 struct WindowState {
-    window: Option<Arc<Window>>,
-    window_attrs: Option<WindowAttributes>,
+    windows: HashMap<WinitWindowId, (WindowId, Arc<Window>)>,
+    pending_windows: Vec<PendingWindow>,
+    window_control: Option<WindowControl>,
     event_loop: Option<winit::event_loop::EventLoop<()>>,
-    event_dispatcher: Option<Box<dyn FnMut(WindowEvent) -> ()>>,
-    window_supplier: Box<dyn Fn(Arc<dyn OutputWindow>) -> Result<(), ThrustlerError>>,
+    event_dispatcher: Option<EventDispatcher>,
+    //the error which has stopped the event loop
+    dispatcher_error: Option<Report<ThrustlerError>>,
+    modifiers: ModifiersState,
+    //raw mouse motion doesn't belong to a window, so it goes to the focused one
+    focused_window: Option<WindowId>,
+    is_composing: bool,
+    //file drops don't come with a position, so the last known one is used
+    cursor_positions: HashMap<WindowId, [f32; 2]>,
+    is_started: bool,
+    //nothing is drawn while the application is in background
+    is_suspended: bool,
+    window_supplier: WindowSupplier,
 }
 
 impl WindowState {
-    fn dispatch_event(&mut self, event: WindowEvent) {
-        self.event_dispatcher.as_mut()
+    fn dispatch_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
+        //the event loop is stopping, the dispatcher mustn't get events anymore
+        if self.dispatcher_error.is_some() {
+            return;
+        }
+
+        let result = self.event_dispatcher.as_mut()
             .expect("Event dispatcher doesn't set up")
             (event);
+
+        if let Err(report) = result {
+            self.stop(event_loop, report);
+            return;
+        }
+        self.apply_window_commands(event_loop);
+    }
+
+    /// Stops the event loop because of the error, it's returned from [`ThrustlerWindow::start`] then.
+    fn stop(&mut self, event_loop: &ActiveEventLoop, report: Report<ThrustlerError>) {
+        self.dispatcher_error = Some(report);
+        self.windows.clear();
+        event_loop.exit();
+    }
+
+    fn apply_window_commands(&mut self, event_loop: &ActiveEventLoop) {
+        let commands = self.window_control.as_ref()
+            .map(|window_control| window_control.take_commands())
+            .unwrap_or_default();
+
+        for (window_id, command) in commands {
+            //commands for closed windows are dropped
+            let Some(window) = self.window(window_id) else {
+                continue;
+            };
+            match command {
+                WindowCommand::SetTitle(title) => window.set_title(&title),
+                WindowCommand::SetCursorVisible(visible) => window.set_cursor_visible(visible),
+                WindowCommand::SetCursorGrab(cursor_grab) => cursor::set_cursor_grab(window, cursor_grab),
+                WindowCommand::SetImeAllowed(allowed) => window.set_ime_allowed(allowed),
+                WindowCommand::SetCursor(cursor) => {
+                    if let Some(cursor) = cursor::to_winit_cursor(event_loop, cursor) {
+                        window.set_cursor(cursor);
+                    }
+                }
+            }
+        }
+    }
+
+    fn window(&self, window_id: WindowId) -> Option<&Arc<Window>> {
+        self.windows.values()
+            .find(|(id, _)| *id == window_id)
+            .map(|(_, window)| window)
     }
 }
 
 impl ApplicationHandler<()> for WindowState {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window = event_loop.create_window(self.window_attrs.take().unwrap()).unwrap();
-        let rc_window = Arc::new(window);
-        let trait_object: Arc<dyn OutputWindow> = rc_window.clone() as Arc<dyn OutputWindow>;
+        //the first resume opens windows, the next ones come after suspending and only recreate surfaces
+        if self.is_started {
+            self.is_suspended = false;
+            let windows = self.windows.values().cloned().collect::<Vec<_>>();
+            for (id, window) in windows {
+                if let Err(report) = self.window_supplier.as_mut()(id, window) {
+                    //scenes are started already, so they are destroyed before the loop is stopped
+                    self.dispatch_event(event_loop, WindowEvent::OnStop);
+                    self.stop(event_loop, report);
+                    return;
+                }
+            }
+            self.dispatch_event(event_loop, WindowEvent::Resumed);
+            return;
+        }
+        self.is_started = true;
+
+        let mut opened_windows = vec![];
+        for pending_window in std::mem::take(&mut self.pending_windows) {
+            let fullscreen = pending_window.fullscreen
+                .and_then(|fullscreen| to_winit_fullscreen(event_loop, fullscreen, &pending_window.window_attrs));
+            let window = match event_loop.create_window(pending_window.window_attrs.with_fullscreen(fullscreen)) {
+                Ok(window) => window,
+                Err(err) => {
+                    let report = Report::new(err)
+                        .attach_printable(format!("Can't create the window {:?}", pending_window.id))
+                        .change_context(ThrustlerError::WindowError);
+                    self.stop(event_loop, report);
+                    return;
+                }
+            };
+            let rc_window = Arc::new(window);
+            let trait_object: Arc<dyn OutputWindow> = rc_window.clone() as Arc<dyn OutputWindow>;
 
-        self.window_supplier.as_mut()(trait_object).expect("Error while window was used for set up backend");
-        self.dispatch_event(WindowEvent::OnStart);
-        self.window = Some(rc_window);
+            if let Err(report) = self.window_supplier.as_mut()(pending_window.id, trait_object) {
+                self.stop(event_loop, report.attach_printable("Error while window was used for set up backend"));
+                return;
+            }
+            opened_windows.push((pending_window.id, rc_window.clone()));
+            self.windows.insert(rc_window.id(), (pending_window.id, rc_window));
+        }
+
+        //the real size is known only now, it depends on the scale factor of the monitor
+        for (id, window) in opened_windows {
+            self.dispatch_event(event_loop, WindowEvent::ScaleFactorChanged(id, window.scale_factor()));
+            self.dispatch_event(event_loop, WindowEvent::Resized(id, to_size(window.inner_size())));
+        }
+        self.dispatch_event(event_loop, WindowEvent::OnStart);
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: winit::event::WindowEvent) {
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        self.is_suspended = true;
+        self.dispatch_event(event_loop, WindowEvent::Suspended);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: WinitWindowId, event: winit::event::WindowEvent) {
+        let Some(id) = self.windows.get(&window_id).map(|(id, _)| *id) else {
+            return;
+        };
+
         match event {
-            winit::event::WindowEvent::CloseRequested => {
-                self.dispatch_event(WindowEvent::OnStop);
-                let _ = self.window.take();
+            winit::event::WindowEvent::CloseRequested if id == WindowId::PRIMARY => {
+                self.dispatch_event(event_loop, WindowEvent::OnStop);
+                self.windows.clear();
                 event_loop.exit();
             }
+            winit::event::WindowEvent::CloseRequested => {
+                self.dispatch_event(event_loop, WindowEvent::WindowClosed(id));
+                self.windows.remove(&window_id);
+            }
             winit::event::WindowEvent::RedrawRequested => {
-                self.dispatch_event(WindowEvent::OnDraw);
+                self.dispatch_event(event_loop, WindowEvent::Redraw(id));
+            }
+            winit::event::WindowEvent::Resized(size) => {
+                self.dispatch_event(event_loop, WindowEvent::Resized(id, to_size(size)));
+            }
+            //a new size comes with a separate event if the window is resized because of the new scale factor
+            winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.dispatch_event(event_loop, WindowEvent::ScaleFactorChanged(id, scale_factor));
+            }
+            winit::event::WindowEvent::Focused(is_focused) => {
+                if is_focused {
+                    self.focused_window = Some(id);
+                } else if self.focused_window == Some(id) {
+                    self.focused_window = None;
+                }
+                self.dispatch_event(event_loop, WindowEvent::Focused(id, is_focused));
+            }
+            winit::event::WindowEvent::Occluded(is_occluded) => {
+                self.dispatch_event(event_loop, WindowEvent::Occluded(id, is_occluded));
+            }
+            winit::event::WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+            winit::event::WindowEvent::KeyboardInput { event, .. } => {
+                let keyboard_event = keyboard::to_keyboard_event(&event, self.modifiers);
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Keyboard(keyboard_event)));
+                //keys which are composed by an input method come as its commits
+                if !self.is_composing {
+                    if let Some(text_event) = text::to_typed_text_event(&event) {
+                        self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Text(text_event)));
+                    }
+                }
+            }
+            winit::event::WindowEvent::Ime(ime) => {
+                if let Some(text_event) = text::to_ime_text_event(ime) {
+                    if let TextEvent::Preedit { text, .. } = &text_event {
+                        self.is_composing = !text.is_empty();
+                    }
+                    self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Text(text_event)));
+                }
+            }
+            winit::event::WindowEvent::CursorMoved { position, .. } => {
+                let mouse_event = mouse::to_cursor_moved_event(position);
+                if let MouseEvent::CursorMoved { position } = mouse_event {
+                    self.cursor_positions.insert(id, position);
+                }
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Mouse(mouse_event)));
+            }
+            winit::event::WindowEvent::CursorEntered { .. } => {
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Mouse(MouseEvent::CursorEntered)));
+            }
+            winit::event::WindowEvent::CursorLeft { .. } => {
+                self.cursor_positions.remove(&id);
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Mouse(MouseEvent::CursorLeft)));
+            }
+            winit::event::WindowEvent::MouseInput { state, button, .. } => {
+                let mouse_event = mouse::to_button_event(state, button);
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Mouse(mouse_event)));
+            }
+            winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                let mouse_event = mouse::to_wheel_event(delta);
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Mouse(mouse_event)));
+            }
+            winit::event::WindowEvent::Touch(touch) => {
+                let touch_event = touch::to_touch_event(touch);
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Touch(touch_event)));
+            }
+            winit::event::WindowEvent::HoveredFile(path) => {
+                let position = self.cursor_positions.get(&id).copied();
+                let file_drop_event = FileDropEvent::Hovered { path, position };
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::FileDrop(file_drop_event)));
+            }
+            winit::event::WindowEvent::HoveredFileCancelled => {
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::FileDrop(FileDropEvent::HoverCancelled)));
+            }
+            winit::event::WindowEvent::DroppedFile(path) => {
+                let position = self.cursor_positions.get(&id).copied();
+                let file_drop_event = FileDropEvent::Dropped { path, position };
+                self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::FileDrop(file_drop_event)));
             }
             _ => {}
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        match self.window.as_ref() {
-            None => (),
-            Some(window) => window.clone().request_redraw()
+    fn device_event(&mut self, event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        if let (DeviceEvent::MouseMotion { delta: (x, y) }, Some(id)) = (event, self.focused_window) {
+            let mouse_event = MouseEvent::Motion { delta: [x as f32, y as f32] };
+            self.dispatch_event(event_loop, WindowEvent::Input(id, InputEvent::Mouse(mouse_event)));
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.is_suspended {
+            return;
+        }
+        //the game goes on once per iteration, whichever windows are visible
+        self.dispatch_event(event_loop, WindowEvent::OnDraw);
+        for (_, window) in self.windows.values() {
+            window.request_redraw();
+        }
+    }
+}
+
+fn to_winit_fullscreen(event_loop: &ActiveEventLoop, fullscreen: Fullscreen, window_attrs: &WindowAttributes) -> Option<WinitFullscreen> {
+    match fullscreen {
+        Fullscreen::Borderless => Some(WinitFullscreen::Borderless(None)),
+        Fullscreen::Exclusive => {
+            let video_mode = event_loop.primary_monitor()
+                .or_else(|| event_loop.available_monitors().next())
+                .and_then(|monitor| closest_video_mode(&monitor, window_attrs));
+            match video_mode {
+                Some(video_mode) => Some(WinitFullscreen::Exclusive(video_mode)),
+                None => {
+                    println!("No video modes for exclusive fullscreen, borderless fullscreen is used");
+                    Some(WinitFullscreen::Borderless(None))
+                }
+            }
         }
     }
 }
 
-pub trait OutputWindow: HasWindowHandle + HasDisplayHandle + Any + Send + Sync {}
+/// The mode with the size closest to the window size, the highest refresh rate is preferred among equal ones.
+fn closest_video_mode(monitor: &MonitorHandle, window_attrs: &WindowAttributes) -> Option<VideoModeHandle> {
+    let [width, height]: [u32; 2] = window_attrs.inner_size
+        .map(|size| size.to_physical::<u32>(monitor.scale_factor()).into())
+        .unwrap_or([0, 0]);
+    monitor.video_modes().min_by_key(|video_mode| {
+        let size = video_mode.size();
+        let size_difference = size.width.abs_diff(width) as u64 + size.height.abs_diff(height) as u64;
+        (size_difference, u32::MAX - video_mode.refresh_rate_millihertz())
+    })
+}
 
-impl OutputWindow for Window {}
\ No newline at end of file
+fn to_size(size: PhysicalSize<u32>) -> Size {
+    Size::new(size.width, size.height)
+}
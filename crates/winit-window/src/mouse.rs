@@ -0,0 +1,34 @@
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, MouseButton as WinitMouseButton, MouseScrollDelta};
+
+use core::input::mouse::{ButtonState, MouseButton, MouseEvent, ScrollDelta};
+
+pub(crate) fn to_cursor_moved_event(position: PhysicalPosition<f64>) -> MouseEvent {
+    MouseEvent::CursorMoved {
+        position: [position.x as f32, position.y as f32],
+    }
+}
+
+pub(crate) fn to_button_event(state: ElementState, button: WinitMouseButton) -> MouseEvent {
+    let button = match button {
+        WinitMouseButton::Left => MouseButton::Left,
+        WinitMouseButton::Right => MouseButton::Right,
+        WinitMouseButton::Middle => MouseButton::Middle,
+        WinitMouseButton::Back => MouseButton::Back,
+        WinitMouseButton::Forward => MouseButton::Forward,
+        WinitMouseButton::Other(code) => MouseButton::Other(code),
+    };
+    let state = match state {
+        ElementState::Pressed => ButtonState::Pressed,
+        ElementState::Released => ButtonState::Released,
+    };
+    MouseEvent::Button { button, state }
+}
+
+pub(crate) fn to_wheel_event(delta: MouseScrollDelta) -> MouseEvent {
+    let delta = match delta {
+        MouseScrollDelta::LineDelta(x, y) => ScrollDelta::Lines([x, y]),
+        MouseScrollDelta::PixelDelta(position) => ScrollDelta::Pixels([position.x as f32, position.y as f32]),
+    };
+    MouseEvent::Wheel { delta }
+}
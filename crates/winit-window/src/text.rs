@@ -0,0 +1,28 @@
+use winit::event::{ElementState, Ime, KeyEvent};
+
+use core::input::text::TextEvent;
+
+/// Text typed by the key, control characters like backspace or enter come only as key events.
+pub(crate) fn to_typed_text_event(event: &KeyEvent) -> Option<TextEvent> {
+    if event.state != ElementState::Pressed {
+        return None;
+    }
+    let text = event.text.as_ref()?
+        .chars()
+        .filter(|char| !char.is_control())
+        .collect::<String>();
+    (!text.is_empty()).then_some(TextEvent::Commit(text))
+}
+
+pub(crate) fn to_ime_text_event(ime: Ime) -> Option<TextEvent> {
+    match ime {
+        Ime::Preedit(text, cursor) => Some(TextEvent::Preedit {
+            text,
+            cursor: cursor.map(|(start, end)| [start, end]),
+        }),
+        Ime::Commit(text) => Some(TextEvent::Commit(text)),
+        //an unfinished composition is dropped with the input method
+        Ime::Disabled => Some(TextEvent::Preedit { text: String::new(), cursor: None }),
+        Ime::Enabled => None,
+    }
+}
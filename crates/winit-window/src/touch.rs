@@ -0,0 +1,17 @@
+use winit::event::{Touch, TouchPhase as WinitTouchPhase};
+
+use core::input::touch::{TouchEvent, TouchId, TouchPhase};
+
+pub(crate) fn to_touch_event(touch: Touch) -> TouchEvent {
+    let phase = match touch.phase {
+        WinitTouchPhase::Started => TouchPhase::Started,
+        WinitTouchPhase::Moved => TouchPhase::Moved,
+        WinitTouchPhase::Ended => TouchPhase::Ended,
+        WinitTouchPhase::Cancelled => TouchPhase::Cancelled,
+    };
+    TouchEvent {
+        id: TouchId(touch.id),
+        phase,
+        position: [touch.location.x as f32, touch.location.y as f32],
+    }
+}
@@ -1,4 +1,6 @@
-use engine::{Backend, Engine, EngineSettings, Scene};
+use std::time::Duration;
+
+use engine::{Backend, Engine, EngineSettings, RenderSettings, Scene, SceneContext, WindowSettings};
 use engine::{GameObject, Vertex};
 use engine::Result;
 use engine::ThrustlerError;
@@ -8,7 +10,16 @@ fn main() -> Result<(), ThrustlerError> {
         Engine::new_with_settings(
             EngineSettings {
                 frames_per_second: 1,
+                stats_log_interval: Some(Duration::from_secs(5)),
                 backend: Backend::Wgpu,
+                window_settings: WindowSettings {
+                    title: "Sierpinski triangles".to_string(),
+                    ..WindowSettings::default()
+                },
+                render_settings: RenderSettings {
+                    sample_count: 4,
+                    ..RenderSettings::default()
+                },
                 ..EngineSettings::default()
             }
         )?
@@ -74,11 +85,11 @@ impl SierpinskiTriangles {
 }
 
 impl Scene for SierpinskiTriangles {
-    fn on_start(&mut self) {
+    fn on_start(&mut self, _context: &mut SceneContext) {
         println!("SierpinskiTriangles start")
     }
 
-    fn on_update(&mut self) {
+    fn on_update(&mut self, _context: &mut SceneContext) {
         if self.current_depth >= self.depth {
             return;
         }
@@ -106,7 +117,7 @@ impl Scene for SierpinskiTriangles {
         });
     }
 
-    fn on_destroy(&mut self) {}
+    fn on_destroy(&mut self, _context: &mut SceneContext) {}
 
     fn get_scene_objects(&self) -> &Vec<GameObject> {
         self.game_objects.as_ref()
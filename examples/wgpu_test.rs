@@ -1,4 +1,4 @@
-use engine::{Backend, Engine, EngineSettings, GameObject, Scene, ThrustlerError, Vertex};
+use engine::{Backend, Engine, EngineSettings, GameObject, Scene, SceneContext, ThrustlerError, Vertex};
 
 fn main() -> engine::Result<(), ThrustlerError> {
     Ok(
@@ -27,11 +27,11 @@ struct Test {
 }
 
 impl Scene for Test {
-    fn on_start(&mut self) {}
+    fn on_start(&mut self, _context: &mut SceneContext) {}
 
-    fn on_update(&mut self) {}
+    fn on_update(&mut self, _context: &mut SceneContext) {}
 
-    fn on_destroy(&mut self) {}
+    fn on_destroy(&mut self, _context: &mut SceneContext) {}
 
     fn get_scene_objects(&self) -> &Vec<GameObject> {
         self.game_objects.as_ref()